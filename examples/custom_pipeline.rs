@@ -0,0 +1,72 @@
+//! Zoom kitty windows from code, without config.toml or the CLI
+//!
+//! Tiled kitty windows grow 1.5x on focus; every event is printed on the way to
+//! the resizer, and a custom `FocusEffect` runs after the built-in ones.
+//! `cargo run --example custom_pipeline --no-default-features --features kitty`
+
+use futures::StreamExt;
+use futures::future::BoxFuture;
+use kitty_focus_tracker::{
+    EffectResult, FocusEffect, KittyRegistry, KittyResizer, NiriRegistry, RegistryConfig, Rules,
+    WindowInfo, ZoomConfig, ZoomProfile,
+};
+use std::sync::Arc;
+
+/// Prints each window the resizer zoomed or restored
+struct PrintFocus;
+
+impl FocusEffect for PrintFocus {
+    fn name(&self) -> &'static str {
+        "print"
+    }
+
+    fn on_focus<'a>(
+        &'a self,
+        window: &'a WindowInfo,
+        pid: i32,
+        profile: &'a ZoomProfile,
+    ) -> BoxFuture<'a, EffectResult> {
+        println!("zoomed {} (pid {}) with {}", window.id, pid, profile.name);
+        Box::pin(async { Ok(()) })
+    }
+
+    fn on_blur<'a>(
+        &'a self,
+        window: &'a WindowInfo,
+        pid: i32,
+        _profile: Option<&'a ZoomProfile>,
+    ) -> BoxFuture<'a, EffectResult> {
+        println!("restored {} (pid {})", window.id, pid);
+        Box::pin(async { Ok(()) })
+    }
+
+    fn on_destroy(&self, pid: i32) {
+        println!("kitty {} closed its last window", pid);
+    }
+
+    fn restore(&self, _everything: bool) -> BoxFuture<'_, EffectResult> {
+        Box::pin(async { Ok(()) })
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let zoom = ZoomConfig {
+        multiplicative: Some(1.5),
+        ..Default::default()
+    };
+    let rules = Rules::new("kitty".to_string(), zoom, Vec::new());
+
+    let kitty_registry = KittyRegistry::new(RegistryConfig::default());
+    kitty_registry.start_reaper().await;
+    let mut resizer =
+        KittyResizer::with_rules(kitty_registry, rules).with_effect(Arc::new(PrintFocus));
+
+    let handle = resizer.handle();
+    let events = NiriRegistry::new()
+        .await?
+        .windows_matching(move |window| handle.considers(window) && !window.is_floating)
+        .inspect(|event| println!("{} {:?}", event.kind(), event.window_id()));
+
+    resizer.process_events(events).await
+}
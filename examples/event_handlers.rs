@@ -0,0 +1,65 @@
+//! Run a user event handler beside the built-in `log` handler
+//!
+//! Needs only the `niri` feature:
+//! `cargo run --example event_handlers --no-default-features --features niri`
+
+use futures::StreamExt;
+use futures::future::BoxFuture;
+use kitty_focus_tracker::handlers::{HandlerConfig, HandlersConfig};
+use kitty_focus_tracker::{
+    EventHandler, EventKind, HandlerRegistry, HandlerResult, NiriEvent, NiriRegistry,
+};
+use std::collections::HashMap;
+
+/// Counts focus changes per app
+#[derive(Default)]
+struct FocusCount {
+    counts: HashMap<String, u32>,
+}
+
+impl EventHandler for FocusCount {
+    fn name(&self) -> &'static str {
+        "focus-count"
+    }
+
+    fn events(&self) -> &[EventKind] {
+        &[EventKind::Focus]
+    }
+
+    fn handle<'a>(&'a mut self, event: &'a NiriEvent) -> BoxFuture<'a, HandlerResult> {
+        Box::pin(async move {
+            let app_id = event
+                .window()
+                .and_then(|window| window.app_id.clone())
+                .ok_or("focused window has no app_id")?;
+            let count = self.counts.entry(app_id.clone()).or_default();
+            *count += 1;
+            println!("{} focused {} times", app_id, count);
+            Ok(())
+        })
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // What `[handler.focus-count]` and `[handler.log]` tables would configure
+    let config = HandlersConfig::from([
+        ("focus-count".to_string(), HandlerConfig::default()),
+        (
+            "log".to_string(),
+            HandlerConfig {
+                events: Some(vec![EventKind::Create, EventKind::Destroy]),
+                ..HandlerConfig::default()
+            },
+        ),
+    ]);
+    let (dispatcher, _) = HandlerRegistry::builtin()
+        .register("focus-count", |_| Ok(Box::new(FocusCount::default())))
+        .build(&config)?
+        .spawn();
+
+    let mut events = dispatcher.tee(NiriRegistry::new().await?.into_events());
+    while events.next().await.is_some() {}
+
+    Ok(())
+}
@@ -0,0 +1,26 @@
+//! Print focus changes as `NiriRegistry` reports them
+//!
+//! Needs only the `niri` feature:
+//! `cargo run --example focus_log --no-default-features --features niri`
+
+use futures::StreamExt;
+use kitty_focus_tracker::{NiriEvent, NiriRegistry};
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut events = NiriRegistry::new().await?.window_events();
+
+    while let Some(event) = events.next().await {
+        match event {
+            NiriEvent::Focus { window, .. } => {
+                println!("focus {} {:?} {:?}", window.id, window.app_id, window.title);
+            }
+            NiriEvent::Blur { window, .. } => {
+                println!("blur  {} {:?}", window.id, window.app_id);
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
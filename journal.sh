@@ -1,5 +1,11 @@
 #!/usr/bin/env bash
 # journal.sh - Stream logs for kitty-zoom service
+#
+# Extra arguments are passed to journalctl, including field matches from the
+# native journald output (run.sh starts the tracker with --journald):
+#   ./journal.sh WINDOW_ID=42
+#   ./journal.sh KITTY_PID=1234 PRIORITY=3
+#   ./journal.sh SPAN_NAME=kitty_command
 
 set -euo pipefail
 
@@ -39,4 +45,6 @@ fi
 # 4. journalctl can also match by syslog identifier (--identifier=${SERVICE_NAME%.service})
 # 5. Using --identifier is more reliable for transient units
 # 6. Binary name "kitty-focus-tracker" defaults to syslog identifier "kitty-focus-tracker"
-# 7. For consistency, both scripts use $SERVICE_NAME identifier
+# 7. For consistency, both scripts use $SERVICE_NAME identifier; with --journald the
+#    tracker sets SYSLOG_IDENTIFIER from ZOOMING_APPNAME itself
+# 8. Structured fields (WINDOW_ID, PID, KITTY_PID, KIND) are only present with --journald
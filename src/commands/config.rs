@@ -0,0 +1,297 @@
+use crate::config::{CliArgs, CliZoomArgs, Config};
+use clap::Subcommand;
+use figment2::Figment;
+use figment2::providers::{Format, Toml};
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Written by `config init`; the example doubles as the commented starter file
+const STARTER_CONFIG: &str = include_str!("../../config.example.toml");
+
+#[derive(Subcommand, Debug)]
+pub enum ConfigCommand {
+    /// Write a commented starter config.toml
+    #[command(name = "init")]
+    Init {
+        /// Where to write it (default: $XDG_CONFIG_HOME/kitty-focus-tracker/config.toml)
+        #[arg(long, value_name = "PATH")]
+        path: Option<PathBuf>,
+
+        /// Overwrite an existing file
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Print the merged config and the layer each value came from
+    #[command(name = "show")]
+    Show,
+
+    /// Validate a config file, including unknown keys and zoom settings
+    #[command(name = "check")]
+    Check {
+        /// File to check (default: $XDG_CONFIG_HOME/kitty-focus-tracker/config.toml)
+        path: Option<PathBuf>,
+    },
+
+    /// Print a JSON Schema for config.toml, for editor completion
+    #[command(name = "schema")]
+    Schema,
+}
+
+pub fn handle_config_command(
+    cmd: ConfigCommand,
+    args: &CliArgs,
+    zoom_args: &CliZoomArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+    match cmd {
+        ConfigCommand::Init { path, force } => init(path, force),
+        ConfigCommand::Show => show(args, zoom_args),
+        ConfigCommand::Check { path } => check(path),
+        ConfigCommand::Schema => {
+            let schema = schemars::schema_for!(Config);
+            println!("{}", serde_json::to_string_pretty(&schema)?);
+            Ok(())
+        }
+    }
+}
+
+fn default_path(path: Option<PathBuf>) -> Result<PathBuf, String> {
+    path.or_else(Config::config_path)
+        .ok_or_else(|| "Config directory not found".to_string())
+}
+
+fn init(path: Option<PathBuf>, force: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let path = default_path(path)?;
+
+    if path.exists() && !force {
+        return Err(format!(
+            "{} already exists, pass --force to overwrite it",
+            path.display()
+        )
+        .into());
+    }
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&path, STARTER_CONFIG)?;
+
+    println!("Wrote {}", path.display());
+    Ok(())
+}
+
+fn show(args: &CliArgs, zoom_args: &CliZoomArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let figment = Config::figment(Some(args), Some(zoom_args));
+    let config = Config::load(Some(args), Some(zoom_args))?;
+
+    let mut entries = Vec::new();
+    flatten("", &serde_json::to_value(&config)?, &mut entries);
+
+    let lines: Vec<String> = entries
+        .iter()
+        .map(|(key, value)| format!("{} = {}", key, value))
+        .collect();
+    let width = lines.iter().map(String::len).max().unwrap_or(0);
+
+    for (line, (key, _)) in lines.iter().zip(&entries) {
+        println!(
+            "{:<width$}  # {}",
+            line,
+            Config::source_of(&figment, key),
+            width = width
+        );
+    }
+
+    Ok(())
+}
+
+/// Leaf values as dotted keys; unset options are left out
+fn flatten(prefix: &str, value: &Value, out: &mut Vec<(String, String)>) {
+    let join = |key: &str| {
+        if prefix.is_empty() {
+            key.to_string()
+        } else {
+            format!("{}.{}", prefix, key)
+        }
+    };
+
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map {
+                flatten(&join(key), value, out);
+            }
+        }
+        Value::Array(items) => {
+            for (i, value) in items.iter().enumerate() {
+                flatten(&join(&i.to_string()), value, out);
+            }
+        }
+        Value::Null => {}
+        other => out.push((prefix.to_string(), other.to_string())),
+    }
+}
+
+fn check(path: Option<PathBuf>) -> Result<(), Box<dyn std::error::Error>> {
+    let path = default_path(path)?;
+    let mut problems = Vec::new();
+
+    match Config::load_file(&path) {
+        Ok(config) => {
+            if let Err(e) = config.metrics.listen_addr() {
+                problems.push(e);
+            }
+        }
+        Err(e) => problems.push(e.to_string()),
+    }
+
+    if let Ok(raw) = read_raw(&path) {
+        problems.extend(
+            unknown_keys(&raw)
+                .into_iter()
+                .map(|key| format!("Unknown key '{}' is ignored", key)),
+        );
+    }
+
+    if problems.is_empty() {
+        println!("{}: ok", path.display());
+        return Ok(());
+    }
+
+    for problem in &problems {
+        eprintln!("{}: {}", path.display(), problem);
+    }
+    std::process::exit(1);
+}
+
+fn read_raw(path: &Path) -> Result<Value, Box<dyn std::error::Error>> {
+    Ok(Figment::new()
+        .merge(Toml::file(path).search(false).required(true))
+        .extract()?)
+}
+
+/// Dotted paths of keys in `raw` that the config schema does not declare
+///
+/// serde ignores unknown keys, so a typo like `addtive` would otherwise pass silently.
+fn unknown_keys(raw: &Value) -> Vec<String> {
+    let schema = serde_json::to_value(schemars::schema_for!(Config)).unwrap_or_default();
+    let mut unknown = Vec::new();
+    collect_unknown(raw, &schema, &schema, "", &mut unknown);
+    unknown
+}
+
+fn collect_unknown(value: &Value, schema: &Value, root: &Value, path: &str, out: &mut Vec<String>) {
+    let schema = resolve(schema, root);
+    let join = |key: &str| {
+        if path.is_empty() {
+            key.to_string()
+        } else {
+            format!("{}.{}", path, key)
+        }
+    };
+
+    match value {
+        Value::Object(map) => {
+            let Some(properties) = schema.get("properties").and_then(Value::as_object) else {
+                return;
+            };
+            for (key, value) in map {
+                match properties.get(key) {
+                    Some(property) => collect_unknown(value, property, root, &join(key), out),
+                    None => out.push(join(key)),
+                }
+            }
+        }
+        Value::Array(items) => {
+            if let Some(item) = schema.get("items") {
+                for (i, value) in items.iter().enumerate() {
+                    collect_unknown(value, item, root, &join(&i.to_string()), out);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Follow `$ref`s and pick the non-null branch of `Option<T>` schemas
+fn resolve<'a>(schema: &'a Value, root: &'a Value) -> &'a Value {
+    if let Some(target) = schema
+        .get("$ref")
+        .and_then(Value::as_str)
+        .and_then(|r| r.strip_prefix('#'))
+        .and_then(|pointer| root.pointer(pointer))
+    {
+        return resolve(target, root);
+    }
+
+    if let Some(branch) = schema
+        .get("anyOf")
+        .and_then(Value::as_array)
+        .and_then(|branches| {
+            branches
+                .iter()
+                .find(|b| b.get("type").and_then(Value::as_str) != Some("null"))
+        })
+    {
+        return resolve(branch, root);
+    }
+
+    schema
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_starter_config_is_valid() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        fs::write(&path, STARTER_CONFIG).unwrap();
+
+        let config = Config::load_file(&path).unwrap();
+        assert!(config.zoom.is_configured());
+        assert!(unknown_keys(&read_raw(&path).unwrap()).is_empty());
+    }
+
+    #[test]
+    fn test_unknown_keys_are_reported() {
+        let raw = serde_json::json!({
+            "app_id": "kitty",
+            "max_retry": 5,
+            "zoom": { "addtive": 6.0, "step_size": 2.0 },
+            "metrics": { "listen": "127.0.0.1:9464" },
+            "rule": [
+                { "app_id": "kitty", "zoom": { "absolute": 16.0 } },
+                { "titel": "^nvim", "zoom": { "factor": 2.0 } }
+            ]
+        });
+
+        assert_eq!(
+            unknown_keys(&raw),
+            vec![
+                "max_retry",
+                "rule.1.titel",
+                "rule.1.zoom.factor",
+                "zoom.addtive"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_flatten_skips_unset_values() {
+        let value = serde_json::json!({
+            "zoom": { "absolute": null, "additive": 6.0 },
+            "rule": [{ "name": "a" }]
+        });
+        let mut entries = Vec::new();
+        flatten("", &value, &mut entries);
+        assert_eq!(
+            entries,
+            vec![
+                ("rule.0.name".to_string(), "\"a\"".to_string()),
+                ("zoom.additive".to_string(), "6.0".to_string()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,515 @@
+use crate::config::Config;
+use crate::kitty::conf_parser::{
+    get_kitty_config_path, parse_font_size, read_config_with_includes, read_settings,
+};
+use crate::kitty::util::{
+    find_kitty_instances, find_kitty_processes, get_kitty_password, kitty_password_path,
+};
+use clap::Parser;
+use kitty_rc::Kitty;
+use kitty_rc::commands::LsCommand;
+use niri_ipc::socket::Socket;
+use niri_ipc::{Request, Response};
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Oldest niri release that emits `WindowFocusTimestampChanged`
+const MIN_NIRI_VERSION: (u32, u32) = (25, 11);
+
+const LISTEN_ON_EXAMPLE: &str = "listen_on unix:${XDG_RUNTIME_DIR}/kitty-{kitty_pid}.sock";
+
+const KITTY_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Parser, Debug)]
+pub struct DoctorCommand {
+    #[arg(short, long, help = "Path to kitty.conf file")]
+    config_path: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Outcome {
+    Pass,
+    Warn,
+    Fail,
+}
+
+#[derive(Debug)]
+struct Check {
+    outcome: Outcome,
+    name: String,
+    detail: String,
+    fix: Option<String>,
+}
+
+impl Check {
+    fn pass(name: impl Into<String>, detail: impl Into<String>) -> Self {
+        Self {
+            outcome: Outcome::Pass,
+            name: name.into(),
+            detail: detail.into(),
+            fix: None,
+        }
+    }
+
+    fn warn(name: impl Into<String>, detail: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self {
+            outcome: Outcome::Warn,
+            name: name.into(),
+            detail: detail.into(),
+            fix: Some(fix.into()),
+        }
+    }
+
+    fn fail(name: impl Into<String>, detail: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self {
+            outcome: Outcome::Fail,
+            name: name.into(),
+            detail: detail.into(),
+            fix: Some(fix.into()),
+        }
+    }
+}
+
+pub async fn handle_doctor_command(cmd: DoctorCommand) -> Result<(), Box<dyn std::error::Error>> {
+    let password = get_kitty_password().ok().filter(|pw| !pw.is_empty());
+
+    let mut checks = vec![check_niri()];
+    checks.extend(check_kitty_conf(
+        cmd.config_path.map(PathBuf::from),
+        password.as_deref(),
+    ));
+    checks.push(check_password());
+    checks.extend(check_kitty_instances(password.as_deref()).await);
+    checks.extend(check_config());
+
+    for check in &checks {
+        let label = match check.outcome {
+            Outcome::Pass => " ok ",
+            Outcome::Warn => "warn",
+            Outcome::Fail => "FAIL",
+        };
+        println!("[{}] {}: {}", label, check.name, check.detail);
+        if let Some(fix) = &check.fix {
+            println!("       fix: {}", fix);
+        }
+    }
+
+    let failures = checks.iter().filter(|c| c.outcome == Outcome::Fail).count();
+    let warnings = checks.iter().filter(|c| c.outcome == Outcome::Warn).count();
+
+    println!();
+    if failures == 0 {
+        println!("No problems found ({} warnings)", warnings);
+        Ok(())
+    } else {
+        println!("{} problems found ({} warnings)", failures, warnings);
+        std::process::exit(1);
+    }
+}
+
+fn check_niri() -> Check {
+    if std::env::var_os("NIRI_SOCKET").is_none() {
+        return Check::fail(
+            "niri",
+            "NIRI_SOCKET is not set",
+            "Run inside a niri session, or import the environment with `systemctl --user import-environment NIRI_SOCKET`",
+        );
+    }
+
+    let mut socket = match Socket::connect() {
+        Ok(socket) => socket,
+        Err(e) => {
+            return Check::fail(
+                "niri",
+                format!("Could not connect to the niri socket: {}", e),
+                "Check that niri is running and NIRI_SOCKET points at its socket",
+            );
+        }
+    };
+
+    let version = match socket.send(Request::Version) {
+        Ok(Ok(Response::Version(version))) => version,
+        Ok(Ok(other)) => {
+            return Check::fail(
+                "niri",
+                format!("Unexpected reply to a version request: {:?}", other),
+                "Update niri",
+            );
+        }
+        Ok(Err(e)) => {
+            return Check::fail(
+                "niri",
+                format!("niri rejected the version request: {}", e),
+                "Update niri",
+            );
+        }
+        Err(e) => {
+            return Check::fail(
+                "niri",
+                format!("Version request failed: {}", e),
+                "Check that niri is running",
+            );
+        }
+    };
+
+    match parse_niri_version(&version) {
+        Some(found) if found >= MIN_NIRI_VERSION => {
+            Check::pass("niri", format!("Connected to niri {}", version))
+        }
+        Some(_) => Check::fail(
+            "niri",
+            format!(
+                "niri {} is too old to report WindowFocusTimestampChanged",
+                version
+            ),
+            format!(
+                "Upgrade niri to {}.{} or newer",
+                MIN_NIRI_VERSION.0, MIN_NIRI_VERSION.1
+            ),
+        ),
+        None => Check::warn(
+            "niri",
+            format!("Connected, but could not parse niri version '{}'", version),
+            format!(
+                "Make sure niri is {}.{} or newer",
+                MIN_NIRI_VERSION.0, MIN_NIRI_VERSION.1
+            ),
+        ),
+    }
+}
+
+fn check_kitty_conf(config_path: Option<PathBuf>, password: Option<&str>) -> Vec<Check> {
+    let path = match config_path.map_or_else(get_kitty_config_path, Ok) {
+        Ok(path) => path,
+        Err(e) => {
+            return vec![Check::fail(
+                "kitty.conf",
+                e,
+                format!(
+                    "Create ~/.config/kitty/kitty.conf with `allow_remote_control password`, `{}` and `font_size`",
+                    LISTEN_ON_EXAMPLE
+                ),
+            )];
+        }
+    };
+
+    let content = match read_config_with_includes(&path) {
+        Ok(content) => content,
+        Err(e) => {
+            return vec![Check::fail("kitty.conf", e, "Make kitty.conf readable")];
+        }
+    };
+
+    let mut checks = Vec::new();
+
+    let allow = read_settings(&content, "allow_remote_control");
+    checks.push(match allow.last().map(String::as_str) {
+        None | Some("no" | "n" | "false") => Check::fail(
+            "remote control",
+            "allow_remote_control is not enabled",
+            "Run `kitty-focus-tracker setup-kitty`, then restart kitty",
+        ),
+        Some("password") => {
+            let passwords = read_settings(&content, "remote_control_password");
+            match password {
+                _ if passwords.is_empty() => Check::fail(
+                    "remote control",
+                    "allow_remote_control is password but no remote_control_password is set",
+                    "Run `kitty-focus-tracker setup-kitty` to add a matching remote_control_password",
+                ),
+                Some(password)
+                    if !passwords
+                        .iter()
+                        .any(|value| first_token(value) == Some(password)) =>
+                {
+                    Check::fail(
+                        "remote control",
+                        "No remote_control_password matches rc.password",
+                        "Make remote_control_password in kitty.conf match ~/.config/kitty/rc.password",
+                    )
+                }
+                _ => Check::pass("remote control", "allow_remote_control password"),
+            }
+        }
+        Some(value) => Check::pass("remote control", format!("allow_remote_control {}", value)),
+    });
+
+    let runtime_dir = std::env::var("XDG_RUNTIME_DIR").unwrap_or_else(|_| "/tmp".to_string());
+    checks.push(match read_settings(&content, "listen_on").last() {
+        None => Check::fail(
+            "listen_on",
+            "kitty.conf does not set listen_on, so kitty exposes no socket",
+            format!(
+                "Run `kitty-focus-tracker setup-kitty` or add `{}`, then restart kitty",
+                LISTEN_ON_EXAMPLE
+            ),
+        ),
+        Some(value) if listen_on_matches(value, Path::new(&runtime_dir)) => {
+            Check::pass("listen_on", value.clone())
+        }
+        Some(value) => Check::fail(
+            "listen_on",
+            format!(
+                "`listen_on {}` does not create {}/kitty-<pid>.sock",
+                value, runtime_dir
+            ),
+            format!("Replace it with `{}` and restart kitty", LISTEN_ON_EXAMPLE),
+        ),
+    });
+
+    checks.push(match parse_font_size(Some(path.clone())) {
+        Ok(size) => Check::pass(
+            "baseline",
+            format!("font_size {} in {}", size, path.display()),
+        ),
+        Err(e) => Check::fail(
+            "baseline",
+            e,
+            "Add `font_size 12.0` (or your preferred size) to kitty.conf",
+        ),
+    });
+
+    checks
+}
+
+fn check_password() -> Check {
+    let Some(path) = kitty_password_path() else {
+        return Check::fail(
+            "rc.password",
+            "Could not determine the config directory",
+            "Set XDG_CONFIG_HOME or HOME",
+        );
+    };
+
+    let metadata = match std::fs::metadata(&path) {
+        Ok(metadata) => metadata,
+        Err(_) => {
+            return Check::fail(
+                "rc.password",
+                format!("{} not found", path.display()),
+                "Run `kitty-focus-tracker setup-kitty` to generate one",
+            );
+        }
+    };
+
+    if get_kitty_password().is_ok_and(|pw| pw.is_empty()) {
+        return Check::fail(
+            "rc.password",
+            format!("{} is empty", path.display()),
+            "Delete it and run `kitty-focus-tracker setup-kitty`",
+        );
+    }
+
+    if metadata.permissions().mode() & 0o077 != 0 {
+        return Check::warn(
+            "rc.password",
+            format!("{} is readable by other users", path.display()),
+            format!("chmod 600 {}", path.display()),
+        );
+    }
+
+    Check::pass("rc.password", path.display().to_string())
+}
+
+async fn check_kitty_instances(password: Option<&str>) -> Vec<Check> {
+    let instances = find_kitty_instances();
+    let mut checks = Vec::new();
+
+    for pid in find_kitty_processes() {
+        if !instances.iter().any(|(socket_pid, _)| *socket_pid == pid) {
+            checks.push(Check::fail(
+                format!("kitty {}", pid),
+                "Running without a remote control socket",
+                "Restart this kitty after fixing listen_on in kitty.conf",
+            ));
+        }
+    }
+
+    if instances.is_empty() && checks.is_empty() {
+        checks.push(Check::warn(
+            "kitty",
+            "No running kitty instances",
+            "Start kitty and run doctor again to check its socket and password",
+        ));
+    }
+
+    for (pid, socket) in instances {
+        let name = format!("kitty {}", pid);
+        let check = match ls_round_trip(&socket, password).await {
+            Ok(windows) => Check::pass(
+                name,
+                format!(
+                    "{} answered ls with {} OS windows",
+                    socket.display(),
+                    windows
+                ),
+            ),
+            Err(e) if e.contains("auth") || e.contains("password") => Check::fail(
+                name,
+                format!("Authentication failed: {}", e),
+                "Make rc.password match remote_control_password in kitty.conf, then restart kitty",
+            ),
+            Err(e) => Check::fail(
+                name,
+                format!("{}: {}", socket.display(), e),
+                "Check allow_remote_control in kitty.conf and restart this kitty",
+            ),
+        };
+        checks.push(check);
+    }
+
+    checks
+}
+
+/// Run `ls` against one kitty socket, returning the number of OS windows
+async fn ls_round_trip(socket: &Path, password: Option<&str>) -> Result<usize, String> {
+    let mut builder = Kitty::builder().socket_path(socket).timeout(KITTY_TIMEOUT);
+    if let Some(password) = password {
+        builder = builder.password(password);
+    }
+    let mut kitty = builder.connect().await.map_err(|e| e.to_string())?;
+
+    let message = LsCommand::builder()
+        .build()
+        .to_message()
+        .map_err(|e| e.to_string())?;
+    let response = kitty.execute(&message).await.map_err(|e| e.to_string());
+    let _ = kitty.close().await;
+    let response = response?;
+
+    if !response.ok {
+        return Err(response
+            .error
+            .unwrap_or_else(|| "kitty refused the command".to_string()));
+    }
+
+    LsCommand::parse_response(&response)
+        .map(|instances| instances.len())
+        .map_err(|e| e.to_string())
+}
+
+fn check_config() -> Vec<Check> {
+    let source = Config::config_path()
+        .filter(|path| path.exists())
+        .map_or("defaults".to_string(), |path| path.display().to_string());
+
+    let config = match Config::load(None, None) {
+        Ok(config) => config,
+        Err(e) => {
+            return vec![Check::fail(
+                "config",
+                e.to_string(),
+                format!("Fix the setting in {} or the ZK_* environment", source),
+            )];
+        }
+    };
+
+    let mut checks = vec![Check::pass("config", format!("Loaded {}", source))];
+
+    if !config.zoom.is_configured() {
+        checks.push(Check::warn(
+            "zoom",
+            "No zoom target configured, focus changes will not resize fonts",
+            "Set one of absolute, additive, multiplicative, physical or columns under [zoom] (or [zoom.focus]/[zoom.blur]), or pass --zoom-additive",
+        ));
+    }
+
+    if let Err(e) = config.metrics.listen_addr() {
+        checks.push(Check::fail(
+            "metrics",
+            e,
+            "Use a loopback address such as 127.0.0.1:9464 for [metrics] listen",
+        ));
+    }
+
+    checks
+}
+
+/// Parse the `major.minor` prefix of niri's version string, e.g. "25.11 (abc1234)"
+fn parse_niri_version(version: &str) -> Option<(u32, u32)> {
+    let token = version.split_whitespace().next()?;
+    let mut parts = token.split('.');
+    let mut number = || -> Option<u32> {
+        let part = parts.next()?;
+        let digits: String = part.chars().take_while(char::is_ascii_digit).collect();
+        digits.parse().ok()
+    };
+    Some((number()?, number()?))
+}
+
+/// Whether a `listen_on` value produces the `kitty-{pid}.sock` path the daemon looks for
+///
+/// kitty substitutes `{kitty_pid}` and environment variables, and appends `-{pid}`
+/// when the placeholder is missing.
+fn listen_on_matches(value: &str, runtime_dir: &Path) -> bool {
+    const SAMPLE_PID: &str = "4242";
+
+    let Some(path) = value.strip_prefix("unix:") else {
+        return false;
+    };
+
+    let runtime = runtime_dir.to_string_lossy();
+    let mut path = path
+        .replace("${XDG_RUNTIME_DIR}", &runtime)
+        .replace("$XDG_RUNTIME_DIR", &runtime);
+    if path.contains("{kitty_pid}") {
+        path = path.replace("{kitty_pid}", SAMPLE_PID);
+    } else {
+        path = format!("{}-{}", path, SAMPLE_PID);
+    }
+
+    Path::new(&path) == runtime_dir.join(format!("kitty-{}.sock", SAMPLE_PID))
+}
+
+/// First word of a kitty setting value, honouring double quotes
+fn first_token(value: &str) -> Option<&str> {
+    match value.strip_prefix('"') {
+        Some(rest) => rest.split('"').next(),
+        None => value.split_whitespace().next(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_niri_version() {
+        assert_eq!(parse_niri_version("25.11 (b35bcae)"), Some((25, 11)));
+        assert_eq!(parse_niri_version("25.08"), Some((25, 8)));
+        assert_eq!(parse_niri_version("26.01-dev"), Some((26, 1)));
+        assert_eq!(parse_niri_version("unknown"), None);
+        assert!(parse_niri_version("25.08").unwrap() < MIN_NIRI_VERSION);
+    }
+
+    #[test]
+    fn test_listen_on_matches() {
+        let runtime = Path::new("/run/user/1000");
+
+        assert!(listen_on_matches(
+            "unix:${XDG_RUNTIME_DIR}/kitty-{kitty_pid}.sock",
+            runtime
+        ));
+        assert!(listen_on_matches(
+            "unix:/run/user/1000/kitty-{kitty_pid}.sock",
+            runtime
+        ));
+        assert!(!listen_on_matches("unix:/tmp/mykitty", runtime));
+        assert!(!listen_on_matches(
+            "unix:${XDG_RUNTIME_DIR}/kitty.sock",
+            runtime
+        ));
+        assert!(!listen_on_matches("tcp:localhost:12345", runtime));
+    }
+
+    #[test]
+    fn test_first_token() {
+        assert_eq!(
+            first_token("\"my secret\" set-font-size"),
+            Some("my secret")
+        );
+        assert_eq!(first_token("hunter2 ls"), Some("hunter2"));
+        assert_eq!(first_token(""), None);
+    }
+}
@@ -1,3 +1,5 @@
+use crate::kitty::registry::{MAX_FONT_SIZE, MIN_FONT_SIZE};
+use crate::kitty::util::find_kitty_instances;
 use clap::Subcommand;
 use kitty_rc::Kitty;
 use kitty_rc::commands::SetFontSizeCommand;
@@ -73,34 +75,6 @@ pub enum FontCommand {
     List,
 }
 
-fn find_kitty_instances() -> Vec<(i32, PathBuf)> {
-    let runtime_dir = std::env::var("XDG_RUNTIME_DIR").unwrap_or_else(|_| "/tmp".to_string());
-    let mut instances = Vec::new();
-
-    if let Ok(entries) = std::fs::read_dir(&runtime_dir) {
-        for entry in entries.flatten() {
-            if let Ok(name) = entry.file_name().into_string() {
-                if name.starts_with("kitty-") && name.ends_with(".sock") {
-                    let pid_str = name
-                        .strip_prefix("kitty-")
-                        .and_then(|s| s.strip_suffix(".sock"));
-                    if let Some(pid_str) = pid_str {
-                        if let Ok(pid) = pid_str.parse::<i32>() {
-                            let socket_path = entry.path();
-                            if std::path::Path::new(&format!("/proc/{}", pid)).exists() {
-                                instances.push((pid, socket_path));
-                            }
-                        }
-                    }
-                }
-            }
-        }
-    }
-
-    instances.sort_by_key(|(pid, _)| *pid);
-    instances
-}
-
 fn get_password() -> Option<String> {
     let config_dir = dirs::config_dir()?.join("kitty");
     let password_path = config_dir.join("rc.password");
@@ -236,6 +210,15 @@ pub async fn handle_font_command(cmd: FontCommand) -> Result<(), Box<dyn std::er
             size,
             all,
         } => {
+            let requested = size;
+            let size = requested.clamp(MIN_FONT_SIZE, MAX_FONT_SIZE);
+            if size != requested {
+                eprintln!(
+                    "Font size {} is outside {}-{}, using {}",
+                    requested, MIN_FONT_SIZE, MAX_FONT_SIZE, size
+                );
+            }
+
             if all {
                 let instances = find_kitty_instances();
                 if instances.is_empty() {
@@ -269,7 +252,10 @@ pub async fn handle_font_command(cmd: FontCommand) -> Result<(), Box<dyn std::er
                         }
                     };
 
-                    let cmd = SetFontSizeCommand::builder().size(size as f64).build().to_message()?;
+                    let cmd = SetFontSizeCommand::builder()
+                        .size(size)
+                        .build()
+                        .to_message()?;
                     let result = kitty.execute(&cmd).await?;
                     if result.ok {
                         println!("PID {}: Font size set to {}", pid, size);
@@ -306,7 +292,10 @@ pub async fn handle_font_command(cmd: FontCommand) -> Result<(), Box<dyn std::er
                     Kitty::builder().socket_path(&socket).connect().await?
                 };
 
-                let cmd = SetFontSizeCommand::builder().size(size as f64).build().to_message()?;
+                let cmd = SetFontSizeCommand::builder()
+                    .size(size)
+                    .build()
+                    .to_message()?;
                 let result = kitty.execute(&cmd).await?;
                 if result.ok {
                     println!("Font size set to {}", size);
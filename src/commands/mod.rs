@@ -1,6 +1,14 @@
 pub mod conf_size;
+pub mod config;
+pub mod doctor;
 pub mod fonts;
+pub mod setup_kitty;
+pub mod status;
 pub mod systemd;
 pub mod zoomer;
 pub use conf_size::ConfSizeCommand;
+pub use config::ConfigCommand;
+pub use doctor::DoctorCommand;
 pub use fonts::FontCommand;
+pub use setup_kitty::SetupKittyCommand;
+pub use status::StatusCommand;
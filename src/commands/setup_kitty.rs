@@ -0,0 +1,312 @@
+use crate::kitty::conf_parser::{read_config_with_includes, read_settings};
+use clap::Parser;
+use similar::TextDiff;
+use std::fs;
+use std::io::Write;
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+
+const SNIPPET_NAME: &str = "kitty-focus-tracker.conf";
+const PASSWORD_NAME: &str = "rc.password";
+const LISTEN_ON: &str = "unix:${XDG_RUNTIME_DIR}/kitty-{kitty_pid}.sock";
+
+/// Same shape as `pwgen -s 48 1`, which the old enable-rc.sh used
+const PASSWORD_LEN: usize = 48;
+const PASSWORD_ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+
+#[derive(Parser, Debug)]
+pub struct SetupKittyCommand {
+    #[arg(long, help = "Show the changes as a diff without writing anything")]
+    dry_run: bool,
+
+    #[arg(
+        long,
+        value_name = "DIR",
+        help = "kitty config directory (default: ~/.config/kitty)"
+    )]
+    kitty_dir: Option<PathBuf>,
+}
+
+/// One file the setup wants to create or rewrite
+#[derive(Debug)]
+struct FileChange {
+    path: PathBuf,
+    old: Option<String>,
+    new: String,
+    mode: Option<u32>,
+    secret: Option<String>,
+}
+
+impl FileChange {
+    fn describe(&self) -> &'static str {
+        match &self.old {
+            None => "create",
+            Some(old) if *old == self.new => "fix permissions of",
+            Some(_) => "update",
+        }
+    }
+
+    fn print_diff(&self) {
+        let mask = |text: &str| match &self.secret {
+            Some(secret) => text.replace(secret.as_str(), "********"),
+            None => text.to_string(),
+        };
+        let old = mask(self.old.as_deref().unwrap_or(""));
+        let new = mask(&self.new);
+        let path = self.path.display().to_string();
+
+        print!(
+            "{}",
+            TextDiff::from_lines(&old, &new).unified_diff().header(
+                if self.old.is_some() {
+                    &path
+                } else {
+                    "/dev/null"
+                },
+                &path
+            )
+        );
+        if let Some(mode) = self.mode {
+            println!("(mode {:o})", mode);
+        }
+    }
+
+    fn apply(&self) -> std::io::Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(false);
+        if let Some(mode) = self.mode {
+            options.mode(mode);
+        }
+        let mut file = options.open(&self.path)?;
+
+        // `mode` only applies on creation, so tighten existing files before the
+        // new contents go in
+        if let Some(mode) = self.mode {
+            file.set_permissions(fs::Permissions::from_mode(mode))?;
+        }
+        file.set_len(0)?;
+        file.write_all(self.new.as_bytes())?;
+        Ok(())
+    }
+}
+
+pub fn handle_setup_kitty_command(
+    cmd: SetupKittyCommand,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let kitty_dir = match cmd.kitty_dir {
+        Some(dir) => dir,
+        None => dirs::config_dir()
+            .ok_or("Config directory not found")?
+            .join("kitty"),
+    };
+
+    let changes = plan(&kitty_dir)?;
+
+    if changes.is_empty() {
+        println!(
+            "kitty remote control is already configured in {}",
+            kitty_dir.display()
+        );
+        return Ok(());
+    }
+
+    if cmd.dry_run {
+        for change in &changes {
+            change.print_diff();
+        }
+        println!();
+        println!("Dry run: {} files would change", changes.len());
+        return Ok(());
+    }
+
+    for change in &changes {
+        println!("{} {}", change.describe(), change.path.display());
+        change.apply()?;
+    }
+
+    let effective = read_config_with_includes(&kitty_dir.join("kitty.conf"))?;
+    for (key, expected) in [
+        ("allow_remote_control", "password"),
+        ("listen_on", LISTEN_ON),
+    ] {
+        let actual = read_settings(&effective, key).pop();
+        if actual.as_deref() != Some(expected) {
+            eprintln!(
+                "Warning: kitty.conf sets `{} {}` after the include, which overrides {}",
+                key,
+                actual.unwrap_or_default(),
+                SNIPPET_NAME
+            );
+        }
+    }
+
+    println!();
+    println!("Restart running kitty instances so they pick up listen_on and the password");
+    Ok(())
+}
+
+/// Work out which files need writing; an empty plan means setup already ran
+fn plan(kitty_dir: &Path) -> Result<Vec<FileChange>, String> {
+    let read = |path: &Path| match fs::read_to_string(path) {
+        Ok(content) => Ok(Some(content)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(format!("Failed to read {}: {}", path.display(), e)),
+    };
+
+    let mut changes = Vec::new();
+
+    let password_path = kitty_dir.join(PASSWORD_NAME);
+    let old_password = read(&password_path)?;
+    let password = match old_password.as_deref().map(str::trim) {
+        Some(existing) if !existing.is_empty() => existing.to_string(),
+        _ => generate_password()?,
+    };
+    let password_file = format!("{}\n", password);
+    let too_open = fs::metadata(&password_path)
+        .is_ok_and(|metadata| metadata.permissions().mode() & 0o077 != 0);
+    if old_password.as_deref().map(str::trim) != Some(password.as_str()) || too_open {
+        changes.push(FileChange {
+            path: password_path,
+            old: old_password,
+            new: password_file,
+            mode: Some(0o600),
+            secret: Some(password.clone()),
+        });
+    }
+
+    let snippet_path = kitty_dir.join(SNIPPET_NAME);
+    let old_snippet = read(&snippet_path)?;
+    let snippet = render_snippet(&password);
+    if old_snippet.as_deref() != Some(snippet.as_str()) {
+        changes.push(FileChange {
+            path: snippet_path,
+            old: old_snippet,
+            new: snippet,
+            mode: Some(0o600),
+            secret: Some(password),
+        });
+    }
+
+    let conf_path = kitty_dir.join("kitty.conf");
+    let old_conf = read(&conf_path)?;
+    if let Some(conf) = ensure_include(old_conf.as_deref().unwrap_or("")) {
+        changes.push(FileChange {
+            path: conf_path,
+            old: old_conf,
+            new: conf,
+            mode: None,
+            secret: None,
+        });
+    }
+
+    Ok(changes)
+}
+
+fn render_snippet(password: &str) -> String {
+    format!(
+        "# Managed by `kitty-focus-tracker setup-kitty`; rerun it instead of editing\n\
+         allow_remote_control password\n\
+         remote_control_password \"{}\"\n\
+         listen_on {}\n",
+        password, LISTEN_ON
+    )
+}
+
+/// kitty.conf with the snippet include appended, or `None` if it is already included
+fn ensure_include(conf: &str) -> Option<String> {
+    let included = read_settings(conf, "include")
+        .iter()
+        .any(|path| Path::new(path).file_name() == Some(SNIPPET_NAME.as_ref()));
+    if included {
+        return None;
+    }
+
+    let mut conf = conf.to_string();
+    if !conf.is_empty() && !conf.ends_with('\n') {
+        conf.push('\n');
+    }
+    if !conf.is_empty() {
+        conf.push('\n');
+    }
+    conf.push_str("# Remote control for kitty-focus-tracker\n");
+    conf.push_str(&format!("include {}\n", SNIPPET_NAME));
+    Some(conf)
+}
+
+fn generate_password() -> Result<String, String> {
+    // Rejection sampling keeps every character equally likely
+    let limit = u8::MAX - (u8::MAX % PASSWORD_ALPHABET.len() as u8);
+    let mut password = String::with_capacity(PASSWORD_LEN);
+    let mut buf = [0u8; 64];
+
+    while password.len() < PASSWORD_LEN {
+        getrandom::fill(&mut buf).map_err(|e| format!("Failed to generate password: {}", e))?;
+        password.extend(
+            buf.iter()
+                .filter(|&&b| b < limit)
+                .map(|&b| PASSWORD_ALPHABET[(b as usize) % PASSWORD_ALPHABET.len()] as char)
+                .take(PASSWORD_LEN - password.len()),
+        );
+    }
+
+    Ok(password)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_password() {
+        let password = generate_password().unwrap();
+        assert_eq!(password.len(), PASSWORD_LEN);
+        assert!(password.bytes().all(|b| PASSWORD_ALPHABET.contains(&b)));
+        assert_ne!(password, generate_password().unwrap());
+    }
+
+    #[test]
+    fn test_ensure_include() {
+        let conf = ensure_include("font_size 12").unwrap();
+        assert_eq!(
+            conf,
+            "font_size 12\n\n# Remote control for kitty-focus-tracker\ninclude kitty-focus-tracker.conf\n"
+        );
+        assert!(ensure_include(&conf).is_none());
+        assert!(ensure_include("include ~/.config/kitty/kitty-focus-tracker.conf\n").is_none());
+    }
+
+    #[test]
+    fn test_plan_is_idempotent() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("kitty.conf"), "font_size 12\n").unwrap();
+
+        let changes = plan(dir.path()).unwrap();
+        assert_eq!(changes.len(), 3);
+        for change in &changes {
+            change.apply().unwrap();
+        }
+
+        let password_path = dir.path().join(PASSWORD_NAME);
+        let mode = fs::metadata(&password_path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+
+        let password = fs::read_to_string(&password_path).unwrap();
+        let effective = read_config_with_includes(&dir.path().join("kitty.conf")).unwrap();
+        assert_eq!(
+            read_settings(&effective, "remote_control_password"),
+            vec![format!("\"{}\"", password.trim())]
+        );
+        assert_eq!(read_settings(&effective, "listen_on"), vec![LISTEN_ON]);
+
+        assert!(plan(dir.path()).unwrap().is_empty());
+
+        fs::set_permissions(&password_path, fs::Permissions::from_mode(0o644)).unwrap();
+        let changes = plan(dir.path()).unwrap();
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].describe(), "fix permissions of");
+    }
+}
@@ -0,0 +1,155 @@
+use crate::config::ZoomTarget;
+use crate::control::{ControlRequest, ControlResponse, DaemonStatus, send_request};
+use clap::Parser;
+
+#[derive(Parser, Debug)]
+pub struct StatusCommand {
+    #[arg(long, help = "Print the raw status as JSON")]
+    json: bool,
+}
+
+pub async fn handle_status_command(cmd: StatusCommand) -> Result<(), Box<dyn std::error::Error>> {
+    let status = match send_request(&ControlRequest::Status).await? {
+        ControlResponse::Status(status) => *status,
+        ControlResponse::Error { message } => return Err(message.into()),
+    };
+
+    if cmd.json {
+        println!("{}", serde_json::to_string_pretty(&status)?);
+    } else {
+        print_status(&status);
+    }
+
+    Ok(())
+}
+
+fn print_status(status: &DaemonStatus) {
+    println!("Daemon PID:    {}", status.pid);
+    println!("Uptime:        {}", format_duration(status.uptime_secs));
+    println!("Tracking:      {}", status.app_id);
+    println!("Zoom:          {}", describe_zoom(status));
+    println!("Baseline font: {}", format_size(status.baseline_font_size));
+    println!();
+
+    if status.instances.is_empty() {
+        println!("No kitty instances seen yet");
+        return;
+    }
+
+    let rows: Vec<[String; 8]> = status
+        .instances
+        .iter()
+        .map(|instance| {
+            let kitty = &instance.kitty;
+            [
+                kitty.kitty_pid.to_string(),
+                kitty
+                    .status
+                    .as_ref()
+                    .map_or("unknown", |s| s.label())
+                    .to_string(),
+                kitty.idle_secs.map_or("-".to_string(), |secs| {
+                    format!("{} ago", format_duration(secs))
+                }),
+                format!(
+                    "{}{}",
+                    format_size(instance.font_size),
+                    if instance.focused { " *" } else { "" }
+                ),
+                format_size(status.baseline_font_size),
+                instance.profile.clone().unwrap_or_else(|| "-".to_string()),
+                instance
+                    .niri_windows
+                    .iter()
+                    .map(|id| id.to_string())
+                    .collect::<Vec<_>>()
+                    .join(","),
+                kitty.socket_path.display().to_string(),
+            ]
+        })
+        .collect();
+
+    let header = [
+        "PID",
+        "STATUS",
+        "LAST USED",
+        "FONT",
+        "BASELINE",
+        "PROFILE",
+        "WINDOWS",
+        "SOCKET",
+    ];
+    let mut widths = header.map(str::len);
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.len());
+        }
+    }
+
+    let format_row = |cells: &[&str]| {
+        cells
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+            .collect::<Vec<_>>()
+            .join("  ")
+            .trim_end()
+            .to_string()
+    };
+
+    println!("{}", format_row(&header));
+    for row in &rows {
+        let cells: Vec<&str> = row.iter().map(String::as_str).collect();
+        println!("{}", format_row(&cells));
+    }
+    println!();
+    println!("* = focused");
+}
+
+fn describe_zoom(status: &DaemonStatus) -> String {
+    let zoom = &status.zoom;
+    if !zoom.is_configured() {
+        return "not configured".to_string();
+    }
+
+    let mut parts = vec![describe_target(&zoom.focus_target())];
+    if let Some(blur) = &zoom.blur {
+        parts[0] = format!("focus {}", parts[0]);
+        parts.push(format!("blur {}", describe_target(blur)));
+    }
+    for (output, target) in &zoom.output {
+        let focus = describe_target(&target.focus);
+        parts.push(match &target.blur {
+            Some(blur) => format!(
+                "on {} focus {} blur {}",
+                output,
+                focus,
+                describe_target(blur)
+            ),
+            None => format!("on {} {}", output, focus),
+        });
+    }
+    format!("{} (step {})", parts.join(", "), zoom.step_size)
+}
+
+fn describe_target(target: &ZoomTarget) -> String {
+    match (target.active_type(), target.value()) {
+        (Some(zoom_type), Some(value)) => format!("{:?} {}", zoom_type, value).to_lowercase(),
+        _ => "baseline".to_string(),
+    }
+}
+
+fn format_size(size: Option<f64>) -> String {
+    size.map_or("-".to_string(), |s| format!("{:.1}", s))
+}
+
+fn format_duration(secs: u64) -> String {
+    let (hours, minutes, seconds) = (secs / 3600, (secs % 3600) / 60, secs % 60);
+    if hours > 0 {
+        format!("{}h{:02}m{:02}s", hours, minutes, seconds)
+    } else if minutes > 0 {
+        format!("{}m{:02}s", minutes, seconds)
+    } else {
+        format!("{}s", seconds)
+    }
+}
@@ -21,6 +21,7 @@ pub fn generate_systemd_service(output: bool) -> std::io::Result<()> {
         std::io::stdout().write_all(b"ExecStart=")?;
         std::io::stdout().write_all(binary_path.as_bytes())?;
         std::io::stdout().write_all(b"\n")?;
+        std::io::stdout().write_all(b"ExecReload=/bin/kill -HUP $MAINPID\n")?;
         std::io::stdout().write_all(b"Environment=RUST_BACKTRACE=full\n")?;
         std::io::stdout().write_all(b"Restart=always\n")?;
         std::io::stdout().write_all(b"\n")?;
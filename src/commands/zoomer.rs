@@ -1,44 +1,182 @@
-use crate::config::{Config, RegistryConfig, Verbosity};
-use crate::kitty::resizer::KittyResizer;
+use crate::config::{Config, ZoomType};
+use crate::control::{StatusContext, start_server};
+use crate::effects::niri::NiriEffects;
+use crate::handlers::HandlerRegistry;
+use crate::kitty::conf_parser::{dynamic_background_opacity, get_kitty_config_path};
+use crate::kitty::{KittyRegistry, KittyResizer};
 use crate::niri::registry::NiriRegistry;
+use crate::reload::{watch_config, watch_kitty_conf};
+use crate::rules::Rules;
+use futures::{StreamExt, future};
+use std::time::Duration;
+use tokio::signal::unix::{SignalKind, signal};
+use tokio::task::{AbortHandle, JoinHandle};
+use tokio::time::timeout;
+use tracing::{info, warn};
+
+/// How long event handlers get to finish their queued events on shutdown
+const HANDLER_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(2);
 
 /// Run focus tracking for a specific app with configurable font adjustments
-pub async fn run_zoomer(
+///
+/// This is the daemon: running without a subcommand and `zoomer` both start
+/// here. `load` re-reads the config on reload; `app_id` stays fixed across reloads.
+pub async fn run_zoomer<F>(
     app_id: String,
-    verbosity: Verbosity,
-    config: Config,
-) -> Result<(), Box<dyn std::error::Error>> {
-    if verbosity.log_window_events() {
-        eprintln!("Starting zoomer for app_id: {}", app_id);
-        if let Some(zoom_type) = config.zoom.active_type() {
-            match zoom_type {
-                crate::config::ZoomType::Absolute => {
-                    eprintln!("Zoom type: Absolute (target: {})", config.zoom.value().unwrap());
-                }
-                crate::config::ZoomType::Additive => {
-                    eprintln!("Zoom type: Additive (amount: {})", config.zoom.value().unwrap());
-                }
-                crate::config::ZoomType::Multiplicative => {
-                    eprintln!("Zoom type: Multiplicative (factor: {})", config.zoom.value().unwrap());
-                }
-            }
-            eprintln!("Step size: {}", config.zoom.step_size);
-        } else {
-            eprintln!("Warning: No zoom configuration set, using default additive +6");
+    mut config: Config,
+    load: F,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    F: Fn() -> Result<Config, Box<dyn std::error::Error>> + Send + 'static,
+{
+    config.app_id = app_id.clone();
+    info!(app_id = %app_id, "Starting zoomer");
+    match config.zoom.active_type() {
+        Some(ZoomType::Absolute) => {
+            info!(
+                target = config.zoom.value(),
+                step_size = config.zoom.step_size,
+                "Zoom type: absolute"
+            );
+        }
+        Some(ZoomType::Additive) => {
+            info!(
+                amount = config.zoom.value(),
+                step_size = config.zoom.step_size,
+                "Zoom type: additive"
+            );
+        }
+        Some(ZoomType::Multiplicative) => {
+            info!(
+                factor = config.zoom.value(),
+                step_size = config.zoom.step_size,
+                "Zoom type: multiplicative"
+            );
+        }
+        Some(ZoomType::Physical) => {
+            info!(
+                points = config.zoom.value(),
+                step_size = config.zoom.step_size,
+                "Zoom type: physical"
+            );
+        }
+        Some(ZoomType::Columns) => {
+            info!(
+                columns = config.zoom.value(),
+                step_size = config.zoom.step_size,
+                "Zoom type: columns"
+            );
+        }
+        None if config.rules.is_empty() && !config.zoom.is_configured() => {
+            warn!("No zoom configuration set, windows will not be resized");
         }
+        None => {}
     }
+    if let Some(blur) = &config.zoom.blur
+        && let Some(zoom_type) = blur.active_type()
+    {
+        info!(?zoom_type, value = blur.value(), "Blur target configured");
+    }
+    if !config.rules.is_empty() {
+        info!(rules = config.rules.len(), "Per-window zoom rules loaded");
+    }
+    if !config.effect.niri.is_empty() {
+        info!(effects = config.effect.niri.len(), "niri effects loaded");
+    }
+    if let Some(dim) = &config.effect.dim {
+        info!(opacity = dim.opacity, "Dimming unfocused windows");
+        if !dynamic_background_opacity(None) {
+            warn!(
+                "kitty.conf does not set `dynamic_background_opacity yes`, kitty will refuse to dim"
+            );
+        }
+    }
+
+    crate::metrics::start_exporters(&config.metrics).await?;
 
-    let registry_config = config.to_registry_config();
-    let kitty_registry = crate::kitty::KittyRegistry::with_verbosity(registry_config, verbosity);
+    let kitty_registry = KittyRegistry::new(config.to_registry_config());
     kitty_registry.start_reaper().await;
 
-    let niri_registry = NiriRegistry::new_with_verbosity(verbosity).await?;
-    let mut zoomer = KittyResizer::with_zoom_config(kitty_registry, config.zoom);
+    let niri_registry = NiriRegistry::new().await?;
+    let reload_app_id = app_id.clone();
+    let config_updates = watch_config(config.clone(), Config::config_path(), move || {
+        load().map(|config| Config {
+            app_id: reload_app_id.clone(),
+            ..config
+        })
+    });
 
-    let kitty_events =
-        niri_registry.windows_matching(|window| window.app_id.as_deref() == Some(&app_id));
+    let handler_config = config.clone();
+    let handler_updates = config_updates.clone();
+    let handlers = HandlerRegistry::builtin()
+        .register_default(NiriEffects::NAME, move |_| {
+            Ok(Box::new(
+                NiriEffects::from_config(&handler_config)
+                    .with_config_updates(handler_updates.clone()),
+            ))
+        })
+        .build(&config.handlers)?;
+    info!(handlers = ?handlers.names(), "Event handlers enabled");
+    let (dispatcher, handler_tasks) = handlers.spawn();
 
-    zoomer.process_events(kitty_events).await?;
+    let mut zoomer = KittyResizer::with_rules(kitty_registry, Rules::from_config(&config))
+        .with_effects(config.effect.clone())
+        .with_config_updates(config_updates)
+        .with_baseline_updates(watch_kitty_conf(get_kitty_config_path().ok()));
 
-    Ok(())
+    let status_context = StatusContext::new(zoomer.registry().clone(), zoomer.handle());
+    // Held until the daemon exits, which removes the socket file
+    let _control_socket = start_server(status_context).await.unwrap_or_else(|e| {
+        warn!(error = %e, "Failed to start control socket, status unavailable");
+        None
+    });
+
+    let handle = zoomer.handle();
+    let kitty_events = dispatcher
+        .tee(niri_registry.into_events())
+        .filter(move |event| future::ready(handle.wants(event)));
+
+    // The event stream owns the dispatcher, so it is dropped with the select
+    // and handlers see their queues close
+    let result = tokio::select! {
+        result = zoomer.process_events(kitty_events) => result,
+        signal = shutdown_signal() => {
+            info!(signal, "Shutting down");
+            Ok(())
+        }
+    };
+    zoomer.shutdown().await;
+    stop_handlers(handler_tasks).await;
+
+    result
+}
+
+/// Wait for event handlers to finish their queued events, aborting any still
+/// running after `HANDLER_SHUTDOWN_TIMEOUT`
+async fn stop_handlers(tasks: Vec<JoinHandle<()>>) {
+    let aborts: Vec<AbortHandle> = tasks.iter().map(JoinHandle::abort_handle).collect();
+    if timeout(HANDLER_SHUTDOWN_TIMEOUT, future::join_all(tasks))
+        .await
+        .is_err()
+    {
+        warn!(
+            timeout = ?HANDLER_SHUTDOWN_TIMEOUT,
+            "Event handlers did not finish in time, aborting them"
+        );
+        aborts.iter().for_each(AbortHandle::abort);
+    }
+}
+
+/// Resolves with the signal's name on SIGTERM or SIGINT
+async fn shutdown_signal() -> &'static str {
+    let Ok(mut terminate) = signal(SignalKind::terminate()) else {
+        warn!("Failed to install SIGTERM handler");
+        let _ = tokio::signal::ctrl_c().await;
+        return "SIGINT";
+    };
+
+    tokio::select! {
+        _ = terminate.recv() => "SIGTERM",
+        _ = tokio::signal::ctrl_c() => "SIGINT",
+    }
 }
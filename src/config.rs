@@ -1,56 +1,56 @@
-use figment2::{
-    providers::{Env, Format, Toml},
-    Figment,
-};
+use crate::effects::EffectsConfig;
+use crate::effects::kitty::SpacingConfig;
+use crate::handlers::HandlersConfig;
+use crate::niri::types::OutputInfo;
+use crate::rules::Rule;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 use std::time::Duration;
+use tracing::level_filters::LevelFilter;
+
+#[cfg(feature = "cli")]
+mod load;
+
+pub use crate::metrics::MetricsConfig;
+#[cfg(feature = "cli")]
+pub use load::{CliArgs, CliZoomArgs, ConfigSource};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
 pub enum Verbosity {
-    #[default]
     Quiet = 0,
     Error = 1,
     Warn = 2,
+    #[default]
     Info = 3,
     Debug = 4,
     Trace = 5,
 }
 
 impl Verbosity {
+    /// Map a `-v` count onto a level: none logs info, `-v` debug, `-vv` trace
     pub fn from_count(count: u8) -> Self {
         match count {
-            0 => Self::Quiet,
-            1 => Self::Error,
-            2 => Self::Warn,
-            3 => Self::Info,
-            4 => Self::Debug,
-            5 => Self::Trace,
+            0 => Self::Info,
+            1 => Self::Debug,
             _ => Self::Trace,
         }
     }
 
-    pub fn log_all_events(&self) -> bool {
-        *self >= Self::Trace
-    }
-
-    pub fn log_window_events(&self) -> bool {
-        *self >= Self::Debug
+    /// Tracing level filter used when `RUST_LOG` is not set
+    pub fn level_filter(&self) -> LevelFilter {
+        match self {
+            Self::Quiet => LevelFilter::OFF,
+            Self::Error => LevelFilter::ERROR,
+            Self::Warn => LevelFilter::WARN,
+            Self::Info => LevelFilter::INFO,
+            Self::Debug => LevelFilter::DEBUG,
+            Self::Trace => LevelFilter::TRACE,
+        }
     }
 }
 
-/// CLI arguments subset that can override config
-#[derive(Debug, Clone)]
-pub struct CliArgs {
-    pub app_id: String,
-    pub verbosity: Verbosity,
-    pub socket_timeout: u64,
-    pub max_retries: u32,
-    pub max_connections: usize,
-    pub idle_timeout: u64,
-    pub reap_interval: u64,
-}
-
 fn default_app_id() -> String {
     String::from("kitty")
 }
@@ -59,6 +59,10 @@ fn default_verbose() -> bool {
     false
 }
 
+fn default_journald() -> bool {
+    false
+}
+
 fn default_socket_timeout() -> u64 {
     5
 }
@@ -84,7 +88,7 @@ fn default_step_size() -> f64 {
 }
 
 /// Zoom type: absolute, additive, or multiplicative
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ZoomType {
     /// Set to an absolute font size
@@ -93,66 +97,173 @@ pub enum ZoomType {
     Additive,
     /// Multiply current font size (*N)
     Multiplicative,
+    /// Real-world size in points (1/72 inch) on the window's output
+    Physical,
+    /// Font size that fits this many text columns across the window
+    Columns,
 }
 
-/// Zoom configuration
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(default)]
-pub struct ZoomConfig {
-    /// Absolute font size to set on focus
-    pub absolute: Option<f64>,
+fn default_animation_duration() -> u64 {
+    150
+}
 
-    /// Additive amount (e.g., 6 means +6 on focus, -6 on blur)
-    pub additive: Option<f64>,
+fn default_animation_frames() -> u32 {
+    10
+}
 
-    /// Multiplicative factor (e.g., 1.5 means *1.5 on focus, /1.5 on blur)
-    pub multiplicative: Option<f64>,
+/// Easing curve for animated zoom transitions
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum Easing {
+    /// Constant speed
+    Linear,
+    /// Fast start, slowing down towards the target
+    #[default]
+    EaseOut,
+    /// Slow start and end (cubic ease-in-out)
+    Cubic,
+}
 
-    /// Number of steps to apply at once
-    #[serde(default = "default_step_size")]
-    pub step_size: f64,
+impl Easing {
+    /// Progress at time `t`, both in 0.0..=1.0
+    pub fn apply(self, t: f64) -> f64 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::EaseOut => 1.0 - (1.0 - t).powi(2),
+            Easing::Cubic if t < 0.5 => 4.0 * t.powi(3),
+            Easing::Cubic => 1.0 - (-2.0 * t + 2.0).powi(3) / 2.0,
+        }
+    }
 }
 
-impl Default for ZoomConfig {
+/// Animated transitions (`[zoom.animation]`); replaces `step_size` stepping
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct AnimationConfig {
+    /// Length of a full transition in milliseconds
+    #[serde(default = "default_animation_duration")]
+    pub duration_ms: u64,
+
+    /// Number of font sizes sent over the duration
+    #[serde(default = "default_animation_frames")]
+    pub frames: u32,
+
+    /// Easing curve: "linear", "ease-out" or "cubic"
+    pub easing: Easing,
+}
+
+impl Default for AnimationConfig {
     fn default() -> Self {
         Self {
-            absolute: None,
-            additive: None,
-            multiplicative: None,
-            step_size: default_step_size(),
+            duration_ms: default_animation_duration(),
+            frames: default_animation_frames(),
+            easing: Easing::default(),
         }
     }
 }
 
-impl ZoomConfig {
+impl AnimationConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.frames == 0 {
+            return Err("animation frames must be at least 1".to_string());
+        }
+        Ok(())
+    }
+
+    /// Time between two frames, never zero
+    pub fn frame_interval(&self) -> Duration {
+        (Duration::from_millis(self.duration_ms) / self.frames.max(1)).max(Duration::from_millis(1))
+    }
+
+    /// Eased sizes from `from` to `to`, one per frame, ending exactly at `to`
+    pub fn sizes(&self, from: f64, to: f64) -> Vec<f64> {
+        let frames = self.frames.max(1);
+        (1..=frames)
+            .map(|frame| {
+                let progress = self.easing.apply(f64::from(frame) / f64::from(frames));
+                from + (to - from) * progress
+            })
+            .collect()
+    }
+}
+
+/// What a target's font size depends on besides its own settings
+#[derive(Debug, Clone, Copy)]
+pub struct SizeContext<'a> {
+    /// kitty.conf font size
+    pub baseline: f64,
+    /// Output the window is on, for physical sizes and `[zoom.output]` overrides
+    pub output: Option<&'a OutputInfo>,
+    /// Window width in logical pixels, for column targets
+    pub width: Option<f64>,
+    /// Logical pixels one text column takes per point of font size
+    pub cell_width: Option<f64>,
+}
+
+impl SizeContext<'_> {
+    pub fn new(baseline: f64) -> Self {
+        Self {
+            baseline,
+            output: None,
+            width: None,
+            cell_width: None,
+        }
+    }
+}
+
+/// One font size target, relative to the kitty.conf baseline
+///
+/// At most one type may be set; none means the baseline itself.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct ZoomTarget {
+    /// Absolute font size
+    pub absolute: Option<f64>,
+
+    /// Points added to the baseline (negative to shrink)
+    pub additive: Option<f64>,
+
+    /// Factor applied to the baseline (below 1.0 to shrink)
+    pub multiplicative: Option<f64>,
+
+    /// Size in real-world points (1/72 inch) as measured on the output
+    pub physical: Option<f64>,
+
+    /// Number of text columns that should fit across the window
+    pub columns: Option<u32>,
+}
+
+impl ZoomTarget {
     /// Validate that only one zoom type is configured
     /// Returns an error if multiple zoom types are set
     pub fn validate(&self) -> Result<(), String> {
-        let set_types = [
-            self.absolute.is_some(),
-            self.additive.is_some(),
-            self.multiplicative.is_some(),
-        ];
-
-        let count = set_types.iter().filter(|&&x| x).count();
-
-        if count > 1 {
-            let mut types = Vec::new();
-            if self.absolute.is_some() {
-                types.push("absolute");
-            }
-            if self.additive.is_some() {
-                types.push("additive");
-            }
-            if self.multiplicative.is_some() {
-                types.push("multiplicative");
-            }
+        let mut types = Vec::new();
+        if self.absolute.is_some() {
+            types.push("absolute");
+        }
+        if self.additive.is_some() {
+            types.push("additive");
+        }
+        if self.multiplicative.is_some() {
+            types.push("multiplicative");
+        }
+        if self.physical.is_some() {
+            types.push("physical");
+        }
+        if self.columns.is_some() {
+            types.push("columns");
+        }
+
+        if types.len() > 1 {
             return Err(format!(
                 "Multiple zoom types configured: {}. Only one zoom type may be set.",
                 types.join(", ")
             ));
         }
-
+        if self.columns == Some(0) {
+            return Err("columns must be at least 1".to_string());
+        }
         Ok(())
     }
 
@@ -164,6 +275,10 @@ impl ZoomConfig {
             Some(ZoomType::Additive)
         } else if self.multiplicative.is_some() {
             Some(ZoomType::Multiplicative)
+        } else if self.physical.is_some() {
+            Some(ZoomType::Physical)
+        } else if self.columns.is_some() {
+            Some(ZoomType::Columns)
         } else {
             None
         }
@@ -175,37 +290,347 @@ impl ZoomConfig {
             Some(ZoomType::Absolute) => self.absolute,
             Some(ZoomType::Additive) => self.additive,
             Some(ZoomType::Multiplicative) => self.multiplicative,
+            Some(ZoomType::Physical) => self.physical,
+            Some(ZoomType::Columns) => self.columns.map(f64::from),
             None => None,
         }
     }
 
-    /// Check if this config has any zoom type set
-    pub fn is_configured(&self) -> bool {
-        self.active_type().is_some()
+    /// Unclamped font size in `ctx`
+    ///
+    /// Physical sizes are converted with the output's point scale; without one
+    /// (unknown output or size) they are used as plain kitty points. Column
+    /// targets stay at the baseline until the window and cell widths are known.
+    pub fn size(&self, ctx: &SizeContext) -> f64 {
+        let baseline = ctx.baseline;
+        match self.active_type() {
+            Some(ZoomType::Absolute) => self.absolute.unwrap_or(baseline),
+            Some(ZoomType::Additive) => baseline + self.additive.unwrap_or_default(),
+            Some(ZoomType::Multiplicative) => baseline * self.multiplicative.unwrap_or(1.0),
+            Some(ZoomType::Physical) => {
+                let scale = ctx.output.and_then(OutputInfo::point_scale).unwrap_or(1.0);
+                self.physical.unwrap_or(baseline) / scale
+            }
+            Some(ZoomType::Columns) => match (self.columns, ctx.width, ctx.cell_width) {
+                (Some(columns), Some(width), Some(cell_width)) if cell_width > 0.0 => {
+                    width / (f64::from(columns) * cell_width)
+                }
+                _ => baseline,
+            },
+            None => baseline,
+        }
     }
 }
 
-/// CLI arguments subset for zoom configuration
-#[derive(Debug, Clone, Default)]
-pub struct CliZoomArgs {
+/// `[zoom.output."<connector>"]`: the focus target on one output, and
+/// optionally its own blur target
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct OutputZoom {
+    #[serde(flatten)]
+    pub focus: ZoomTarget,
+
+    /// Target for unfocused windows on this output (`[zoom.output."DP-1".blur]`);
+    /// default: `[zoom.blur]`
+    pub blur: Option<ZoomTarget>,
+}
+
+impl OutputZoom {
+    fn validate(&self) -> Result<(), String> {
+        self.focus.validate()?;
+        if let Some(blur) = &self.blur {
+            blur.validate().map_err(|e| format!("blur: {}", e))?;
+        }
+        Ok(())
+    }
+
+    fn targets(&self) -> impl Iterator<Item = &ZoomTarget> {
+        std::iter::once(&self.focus).chain(&self.blur)
+    }
+}
+
+/// Zoom configuration
+///
+/// `absolute`/`additive`/`multiplicative` directly under `[zoom]` are shorthand
+/// for `[zoom.focus]`. Focused windows go to the focus target and unfocused ones
+/// to the blur target; either defaults to the kitty.conf baseline.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct ZoomConfig {
+    /// Absolute font size to set on focus
     pub absolute: Option<f64>,
+
+    /// Additive amount (e.g., 6 means +6 on focus, -6 on blur)
     pub additive: Option<f64>,
+
+    /// Multiplicative factor (e.g., 1.5 means *1.5 on focus, /1.5 on blur)
     pub multiplicative: Option<f64>,
-    pub step_size: Option<f64>,
+
+    /// Real-world size in points on focus, whatever the output's density
+    pub physical: Option<f64>,
+
+    /// Number of text columns to fit across a focused window
+    pub columns: Option<u32>,
+
+    /// Target for focused windows (`[zoom.focus]`), instead of the shorthand above
+    pub focus: Option<ZoomTarget>,
+
+    /// Target for unfocused windows (`[zoom.blur]`); default: the baseline
+    pub blur: Option<ZoomTarget>,
+
+    /// Focus and blur targets for windows on specific outputs (`[zoom.output."DP-1"]`)
+    pub output: BTreeMap<String, OutputZoom>,
+
+    /// Number of steps to apply at once
+    #[serde(default = "default_step_size")]
+    pub step_size: f64,
+
+    /// Animate transitions over time instead of stepping
+    pub animation: Option<AnimationConfig>,
+
+    /// Scale the niri column with the font so the text grid keeps its size,
+    /// restoring the width on blur
+    pub constant_grid: bool,
+
+    /// kitty padding and margin while focused (`[zoom.spacing]`)
+    pub spacing: Option<SpacingConfig>,
+
+    /// Never shrink a window below this font size
+    pub min_font_size: Option<f64>,
+
+    /// Never grow a window above this font size
+    pub max_font_size: Option<f64>,
+}
+
+impl Default for ZoomConfig {
+    fn default() -> Self {
+        Self {
+            absolute: None,
+            additive: None,
+            multiplicative: None,
+            physical: None,
+            columns: None,
+            focus: None,
+            blur: None,
+            output: BTreeMap::new(),
+            step_size: default_step_size(),
+            animation: None,
+            constant_grid: false,
+            spacing: None,
+            min_font_size: None,
+            max_font_size: None,
+        }
+    }
+}
+
+impl ZoomConfig {
+    /// Validate the focus and blur targets, the animation and the font size limits
+    pub fn validate(&self) -> Result<(), String> {
+        let shorthand = self.shorthand();
+        shorthand.validate()?;
+
+        if let Some(focus) = &self.focus {
+            if shorthand.active_type().is_some() {
+                return Err(
+                    "Set the focus zoom either directly under [zoom] or in [zoom.focus], not both"
+                        .to_string(),
+                );
+            }
+            focus
+                .validate()
+                .map_err(|e| format!("[zoom.focus]: {}", e))?;
+        }
+        if let Some(blur) = &self.blur {
+            blur.validate().map_err(|e| format!("[zoom.blur]: {}", e))?;
+        }
+        for (name, target) in &self.output {
+            target
+                .validate()
+                .map_err(|e| format!("[zoom.output.\"{}\"]: {}", name, e))?;
+        }
+        if let Some(animation) = &self.animation {
+            animation.validate()?;
+        }
+        if self.constant_grid
+            && std::iter::once(self.focus_target())
+                .chain(self.blur.clone())
+                .chain(self.output.values().flat_map(OutputZoom::targets).cloned())
+                .any(|target| target.active_type() == Some(ZoomType::Columns))
+        {
+            return Err(
+                "constant_grid resizes the window, so it cannot be combined with a columns target"
+                    .to_string(),
+            );
+        }
+
+        if let Some(spacing) = &self.spacing {
+            spacing
+                .validate()
+                .map_err(|e| format!("[zoom.spacing]: {}", e))?;
+        }
+
+        if let Some(min) = self.min_font_size
+            && min <= 0.0
+        {
+            return Err(format!("min_font_size must be positive, got {}", min));
+        }
+        if let (Some(min), Some(max)) = (self.min_font_size, self.max_font_size)
+            && min > max
+        {
+            return Err(format!(
+                "min_font_size {} is larger than max_font_size {}",
+                min, max
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn shorthand(&self) -> ZoomTarget {
+        ZoomTarget {
+            absolute: self.absolute,
+            additive: self.additive,
+            multiplicative: self.multiplicative,
+            physical: self.physical,
+            columns: self.columns,
+        }
+    }
+
+    /// The focus target: `[zoom.focus]`, or the shorthand directly under `[zoom]`
+    pub fn focus_target(&self) -> ZoomTarget {
+        self.focus.clone().unwrap_or_else(|| self.shorthand())
+    }
+
+    /// The focus target on `output`, honouring its `[zoom.output]` override
+    pub fn focus_target_on(&self, output: Option<&OutputInfo>) -> ZoomTarget {
+        self.output_zoom(output)
+            .map(|zoom| zoom.focus.clone())
+            .unwrap_or_else(|| self.focus_target())
+    }
+
+    /// The blur target on `output`, if any: its `[zoom.output]` override's
+    /// `blur`, or `[zoom.blur]`
+    pub fn blur_target_on(&self, output: Option<&OutputInfo>) -> Option<&ZoomTarget> {
+        self.output_zoom(output)
+            .and_then(|zoom| zoom.blur.as_ref())
+            .or(self.blur.as_ref())
+    }
+
+    fn output_zoom(&self, output: Option<&OutputInfo>) -> Option<&OutputZoom> {
+        output.and_then(|output| self.output.get(&output.name))
+    }
+
+    /// Get the active zoom type of the focus target
+    pub fn active_type(&self) -> Option<ZoomType> {
+        self.focus_target().active_type()
+    }
+
+    /// Get the zoom value of the focus target
+    pub fn value(&self) -> Option<f64> {
+        self.focus_target().value()
+    }
+
+    /// Zoom type of the blur target, falling back to the focus type
+    ///
+    /// Used to pick the stepping of blur transitions.
+    pub fn blur_type(&self) -> Option<ZoomType> {
+        self.blur
+            .as_ref()
+            .and_then(ZoomTarget::active_type)
+            .or_else(|| self.active_type())
+    }
+
+    /// Check if this config changes the font size on focus or blur at all
+    pub fn is_configured(&self) -> bool {
+        self.active_type().is_some()
+            || self
+                .output
+                .values()
+                .flat_map(OutputZoom::targets)
+                .any(|t| t.active_type().is_some())
+            || self
+                .blur
+                .as_ref()
+                .is_some_and(|b| b.active_type().is_some())
+    }
+
+    /// Keep `size` within `min_font_size`/`max_font_size`
+    pub fn clamp(&self, size: f64) -> f64 {
+        let size = self.min_font_size.map_or(size, |min| size.max(min));
+        self.max_font_size.map_or(size, |max| size.min(max))
+    }
+
+    /// Font size for a focused window in `ctx`, clamped
+    ///
+    /// `None` if zoom is not configured at all.
+    pub fn focus_size(&self, ctx: &SizeContext) -> Option<f64> {
+        self.is_configured()
+            .then(|| self.clamp(self.focus_target_on(ctx.output).size(ctx)))
+    }
+
+    /// Font size for an unfocused window in `ctx`, clamped
+    pub fn blur_size(&self, ctx: &SizeContext) -> f64 {
+        self.clamp(
+            self.blur_target_on(ctx.output)
+                .map_or(ctx.baseline, |b| b.size(ctx)),
+        )
+    }
+
+    /// Font sizes to send when going from `from` to `to`, ending exactly at `to`
+    ///
+    /// Intermediate sizes are `step_size` points apart, or a factor of
+    /// `step_size` apart when `kind` (the type of the target being moved to) is
+    /// multiplicative. Empty if nothing changes.
+    pub fn steps(&self, from: f64, to: f64, kind: Option<ZoomType>) -> Vec<f64> {
+        const MAX_STEPS: usize = 64;
+
+        if (to - from).abs() < f64::EPSILON {
+            return Vec::new();
+        }
+
+        let grow = to > from;
+        let step = self.step_size;
+        let next = |size: f64| match kind {
+            Some(ZoomType::Multiplicative) if step > 1.0 => {
+                Some(if grow { size * step } else { size / step })
+            }
+            Some(
+                ZoomType::Absolute | ZoomType::Additive | ZoomType::Physical | ZoomType::Columns,
+            ) if step > 0.0 => Some(if grow { size + step } else { size - step }),
+            _ => None,
+        };
+
+        let mut sizes = Vec::new();
+        let mut size = from;
+        while sizes.len() < MAX_STEPS - 1
+            && let Some(next) = next(size)
+            && (if grow { next < to } else { next > to })
+        {
+            sizes.push(next);
+            size = next;
+        }
+        sizes.push(to);
+        sizes
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// kitty-focus-tracker configuration (config.toml)
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(default)]
 pub struct Config {
     /// Application ID to track (e.g., "kitty")
     #[serde(default = "default_app_id")]
     pub app_id: String,
 
-    /// Enable verbose logging
+    /// Enable verbose logging (raises the log level to at least debug)
     #[serde(default = "default_verbose")]
     pub verbose: bool,
 
-    /// Verbosity level (0-5, higher = more verbose)
+    /// Send logs to journald with structured fields instead of stderr
+    #[serde(default = "default_journald")]
+    pub journald: bool,
+
+    /// Verbosity level from the CLI `-v` count
     #[serde(skip)]
     pub verbosity: Verbosity,
 
@@ -231,6 +656,20 @@ pub struct Config {
 
     /// Zoom configuration
     pub zoom: ZoomConfig,
+
+    /// Metrics exporters
+    pub metrics: MetricsConfig,
+
+    /// Per-window zoom rules (`[[rule]]` tables), first match wins
+    #[serde(rename = "rule")]
+    pub rules: Vec<Rule>,
+
+    /// Actions performed alongside zoom when focus changes
+    pub effect: EffectsConfig,
+
+    /// Event handlers to enable and configure, by name (`[handler.<name>]` tables)
+    #[serde(rename = "handler")]
+    pub handlers: HandlersConfig,
 }
 
 impl Default for Config {
@@ -238,6 +677,7 @@ impl Default for Config {
         Self {
             app_id: default_app_id(),
             verbose: default_verbose(),
+            journald: default_journald(),
             verbosity: Verbosity::Info,
             socket_timeout_secs: default_socket_timeout(),
             max_retries: default_max_retries(),
@@ -245,94 +685,60 @@ impl Default for Config {
             idle_timeout_secs: default_idle_timeout(),
             reap_interval_secs: default_reap_interval(),
             zoom: ZoomConfig::default(),
+            metrics: MetricsConfig::default(),
+            rules: Vec::new(),
+            effect: EffectsConfig::default(),
+            handlers: HandlersConfig::new(),
         }
     }
 }
 
 impl Config {
-    /// Load configuration from multiple sources in order:
-    /// 1. Default values
-    /// 2. Config file at $XDG_CONFIG_HOME/kitty-focus-tracker/config.toml
-    /// 3. Environment variables (ZK_* prefix)
-    /// 4. CLI args (if provided)
-    pub fn load(
-        args: Option<&CliArgs>,
-        zoom_args: Option<&CliZoomArgs>,
-    ) -> Result<Self, Box<dyn std::error::Error>> {
-        let mut figment = Figment::new();
-
-        // Add config file if it exists
-        if let Some(config_path) = Self::config_path() {
-            if config_path.exists() {
-                if let Some(path_str) = config_path.to_str() {
-                    figment = figment.merge(Toml::file(path_str));
-                }
-            }
-        }
-
-        // Add environment variables with ZK_ prefix
-        figment = figment.merge(Env::prefixed("ZK_").split("__"));
+    /// Check the zoom config, every rule and the effects
+    pub fn validate(&self) -> Result<(), String> {
+        self.zoom
+            .validate()
+            .map_err(|e| format!("Invalid zoom configuration: {}", e))?;
 
-        // Add CLI args if provided
-        if let Some(args) = args {
-            if !args.app_id.is_empty() {
-                figment = figment.merge(("app_id", &args.app_id));
-            }
-            if args.verbosity != Verbosity::default() {
-                figment = figment.merge(("verbose", true));
-            }
-            figment = figment.merge(("socket_timeout_secs", args.socket_timeout));
-            figment = figment.merge(("max_retries", args.max_retries));
-            figment = figment.merge(("max_connections", args.max_connections));
-            figment = figment.merge(("idle_timeout_secs", args.idle_timeout));
-            figment = figment.merge(("reap_interval_secs", args.reap_interval));
+        for (i, rule) in self.rules.iter().enumerate() {
+            rule.validate().map_err(|e| {
+                format!(
+                    "Invalid rule '{}': {}",
+                    rule.name
+                        .clone()
+                        .unwrap_or_else(|| format!("rule-{}", i + 1)),
+                    e
+                )
+            })?;
         }
 
-        // Extract base config
-        let mut config: Config = figment.extract()?;
-
-        // Validate zoom config from file
-        config
-            .zoom
+        self.effect
             .validate()
-            .map_err(|e| format!("Invalid zoom configuration: {}", e))?;
+            .map_err(|e| format!("Invalid effect configuration: {}", e))?;
 
-        // Apply CLI zoom args if provided (overrides config file)
-        if let Some(zoom) = zoom_args {
-            if zoom.absolute.is_some() {
-                config.zoom.absolute = zoom.absolute;
-                config.zoom.additive = None;
-                config.zoom.multiplicative = None;
-            }
-            if zoom.additive.is_some() {
-                config.zoom.additive = zoom.additive;
-                config.zoom.absolute = None;
-                config.zoom.multiplicative = None;
-            }
-            if zoom.multiplicative.is_some() {
-                config.zoom.multiplicative = zoom.multiplicative;
-                config.zoom.absolute = None;
-                config.zoom.additive = None;
-            }
-            if zoom.step_size.is_some() {
-                config.zoom.step_size = zoom.step_size.unwrap();
-            }
-
-            // Validate after CLI overrides
-            config
-                .zoom
+        for (name, handler) in &self.handlers {
+            handler
                 .validate()
-                .map_err(|e| format!("Invalid zoom configuration: {}", e))?;
+                .map_err(|e| format!("Invalid handler '{}': {}", name, e))?;
         }
 
-        Ok(config)
+        Ok(())
     }
 
     /// Get path to config file
-    fn config_path() -> Option<PathBuf> {
+    pub fn config_path() -> Option<PathBuf> {
         dirs::config_dir().map(|dir| dir.join("kitty-focus-tracker").join("config.toml"))
     }
 
+    /// Verbosity after folding in the `verbose` flag from file or env
+    pub fn effective_verbosity(&self) -> Verbosity {
+        if self.verbose {
+            self.verbosity.max(Verbosity::Debug)
+        } else {
+            self.verbosity
+        }
+    }
+
     /// Convert to RegistryConfig for KittyRegistry
     pub fn to_registry_config(&self) -> RegistryConfig {
         RegistryConfig {
@@ -341,7 +747,6 @@ impl Config {
             max_connections: self.max_connections,
             idle_timeout: Duration::from_secs(self.idle_timeout_secs),
             reap_interval: Duration::from_secs(self.reap_interval_secs),
-            verbose: self.verbose || self.verbosity >= Verbosity::Debug,
         }
     }
 }
@@ -354,7 +759,6 @@ pub struct RegistryConfig {
     pub max_connections: usize,
     pub idle_timeout: Duration,
     pub reap_interval: Duration,
-    pub verbose: bool,
 }
 
 impl Default for RegistryConfig {
@@ -365,7 +769,6 @@ impl Default for RegistryConfig {
             max_connections: default_max_connections(),
             idle_timeout: Duration::from_secs(default_idle_timeout()),
             reap_interval: Duration::from_secs(default_reap_interval()),
-            verbose: default_verbose(),
         }
     }
 }
@@ -373,11 +776,15 @@ impl Default for RegistryConfig {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use figment2::Figment;
+    use figment2::providers::{Format, Toml};
 
     #[test]
     fn test_zoom_config_validate_single_type() {
-        let mut config = ZoomConfig::default();
-        config.additive = Some(6.0);
+        let config = ZoomConfig {
+            additive: Some(6.0),
+            ..Default::default()
+        };
         assert!(config.validate().is_ok());
         assert_eq!(config.active_type(), Some(ZoomType::Additive));
         assert_eq!(config.value(), Some(6.0));
@@ -385,16 +792,20 @@ mod tests {
 
     #[test]
     fn test_zoom_config_validate_multiple_types_error() {
-        let mut config = ZoomConfig::default();
-        config.additive = Some(6.0);
-        config.multiplicative = Some(1.5);
+        let config = ZoomConfig {
+            additive: Some(6.0),
+            multiplicative: Some(1.5),
+            ..Default::default()
+        };
         assert!(config.validate().is_err());
     }
 
     #[test]
     fn test_zoom_config_validate_absolute() {
-        let mut config = ZoomConfig::default();
-        config.absolute = Some(18.0);
+        let config = ZoomConfig {
+            absolute: Some(18.0),
+            ..Default::default()
+        };
         assert!(config.validate().is_ok());
         assert_eq!(config.active_type(), Some(ZoomType::Absolute));
         assert_eq!(config.value(), Some(18.0));
@@ -402,8 +813,10 @@ mod tests {
 
     #[test]
     fn test_zoom_config_validate_multiplicative() {
-        let mut config = ZoomConfig::default();
-        config.multiplicative = Some(1.5);
+        let config = ZoomConfig {
+            multiplicative: Some(1.5),
+            ..Default::default()
+        };
         assert!(config.validate().is_ok());
         assert_eq!(config.active_type(), Some(ZoomType::Multiplicative));
         assert_eq!(config.value(), Some(1.5));
@@ -416,4 +829,325 @@ mod tests {
         assert_eq!(config.active_type(), None);
         assert_eq!(config.value(), None);
     }
+
+    #[test]
+    fn test_zoom_config_clamps_focus_size() {
+        let config = ZoomConfig {
+            multiplicative: Some(4.0),
+            min_font_size: Some(8.0),
+            max_font_size: Some(30.0),
+            ..Default::default()
+        };
+        assert!(config.validate().is_ok());
+        assert_eq!(config.focus_size(&SizeContext::new(10.0)), Some(30.0));
+        assert_eq!(config.focus_size(&SizeContext::new(5.0)), Some(20.0));
+        assert_eq!(config.clamp(-3.0), 8.0);
+
+        let inverted = ZoomConfig {
+            min_font_size: Some(30.0),
+            max_font_size: Some(8.0),
+            ..Default::default()
+        };
+        assert!(inverted.validate().is_err());
+    }
+
+    #[test]
+    fn test_zoom_config_steps() {
+        let additive = ZoomConfig {
+            additive: Some(6.0),
+            step_size: 3.0,
+            ..Default::default()
+        };
+        let kind = additive.active_type();
+        assert_eq!(additive.steps(12.0, 18.0, kind), vec![15.0, 18.0]);
+        assert_eq!(additive.steps(18.0, 12.0, kind), vec![15.0, 12.0]);
+        assert_eq!(additive.steps(12.0, 12.0, kind), Vec::<f64>::new());
+
+        let multiplicative = ZoomConfig {
+            multiplicative: Some(9.0),
+            step_size: 2.0,
+            ..Default::default()
+        };
+        assert_eq!(
+            multiplicative.steps(1.0, 9.0, multiplicative.active_type()),
+            vec![2.0, 4.0, 8.0, 9.0]
+        );
+
+        // A factor of 1 would never get anywhere, so jump straight to the target
+        let unit = ZoomConfig {
+            multiplicative: Some(1.5),
+            ..Default::default()
+        };
+        assert_eq!(unit.steps(12.0, 18.0, unit.active_type()), vec![18.0]);
+    }
+
+    #[test]
+    fn test_zoom_config_focus_and_blur_targets() {
+        let config: ZoomConfig = Figment::new()
+            .merge(Toml::string(
+                "step_size = 2.0\n[focus]\nadditive = 4.0\n[blur]\nmultiplicative = 0.5\n",
+            ))
+            .extract()
+            .unwrap();
+        assert!(config.validate().is_ok());
+        assert!(config.is_configured());
+        assert_eq!(config.active_type(), Some(ZoomType::Additive));
+        assert_eq!(config.blur_type(), Some(ZoomType::Multiplicative));
+        assert_eq!(config.focus_size(&SizeContext::new(12.0)), Some(16.0));
+        assert_eq!(config.blur_size(&SizeContext::new(12.0)), 6.0);
+
+        // Blur is its own state, not the inverse of focus
+        assert_eq!(config.steps(16.0, 6.0, config.blur_type()), vec![8.0, 6.0]);
+
+        // Blur alone shrinks unfocused windows and leaves focused ones at the baseline
+        let blur_only = ZoomConfig {
+            blur: Some(ZoomTarget {
+                additive: Some(-2.0),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert!(blur_only.is_configured());
+        assert_eq!(blur_only.focus_size(&SizeContext::new(12.0)), Some(12.0));
+        assert_eq!(blur_only.blur_size(&SizeContext::new(12.0)), 10.0);
+        assert_eq!(blur_only.blur_type(), Some(ZoomType::Additive));
+
+        // Without a blur target windows return to the baseline
+        let focus_only = ZoomConfig {
+            absolute: Some(20.0),
+            ..Default::default()
+        };
+        assert_eq!(focus_only.blur_size(&SizeContext::new(12.0)), 12.0);
+        assert_eq!(focus_only.blur_type(), Some(ZoomType::Absolute));
+    }
+
+    #[test]
+    fn test_animation_frames_follow_the_easing_curve() {
+        let config: ZoomConfig = Figment::new()
+            .merge(Toml::string(
+                "additive = 8.0\n[animation]\nduration_ms = 100\nframes = 4\neasing = \"linear\"\n",
+            ))
+            .extract()
+            .unwrap();
+        assert!(config.validate().is_ok());
+
+        let animation = config.animation.unwrap();
+        assert_eq!(animation.frame_interval(), Duration::from_millis(25));
+        assert_eq!(animation.sizes(12.0, 20.0), vec![14.0, 16.0, 18.0, 20.0]);
+        assert_eq!(animation.sizes(20.0, 12.0), vec![18.0, 16.0, 14.0, 12.0]);
+
+        let ease_out = AnimationConfig {
+            frames: 2,
+            ..Default::default()
+        };
+        assert_eq!(ease_out.sizes(10.0, 18.0), vec![16.0, 18.0]);
+
+        for easing in [Easing::Linear, Easing::EaseOut, Easing::Cubic] {
+            assert_eq!(easing.apply(0.0), 0.0);
+            assert_eq!(easing.apply(1.0), 1.0);
+        }
+        assert!(Easing::Cubic.apply(0.25) < Easing::Linear.apply(0.25));
+        assert!(Easing::EaseOut.apply(0.25) > Easing::Linear.apply(0.25));
+
+        let no_frames = ZoomConfig {
+            animation: Some(AnimationConfig {
+                frames: 0,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert!(no_frames.validate().is_err());
+    }
+
+    #[test]
+    fn test_zoom_config_output_overrides_and_physical_size() {
+        let config: ZoomConfig = Figment::new()
+            .merge(Toml::string(
+                "physical = 10.0\n[output.eDP-1]\nabsolute = 14.0\n[output.HDMI-A-1]\nadditive = 1.0\nphysical = 9.0\n",
+            ))
+            .extract()
+            .unwrap();
+        assert!(config.validate().unwrap_err().contains("HDMI-A-1"));
+
+        let mut config = config;
+        config.output.remove("HDMI-A-1");
+        assert!(config.validate().is_ok());
+        assert_eq!(config.active_type(), Some(ZoomType::Physical));
+
+        let output = |name: &str, physical_size| OutputInfo {
+            name: name.to_string(),
+            scale: 1.0,
+            logical_size: Some((1920, 1080)),
+            physical_size,
+        };
+
+        // The override wins on its output, whatever its density
+        let laptop = output("eDP-1", Some((309, 174)));
+        assert_eq!(
+            config.focus_size(&SizeContext {
+                output: Some(&laptop),
+                ..SizeContext::new(12.0)
+            }),
+            Some(14.0)
+        );
+
+        // ~0.16mm pixels make a kitty point ~0.6 real points, so it needs ~16.4
+        let desk = output("DP-1", Some((309, 174)));
+        let size = config
+            .focus_size(&SizeContext {
+                output: Some(&desk),
+                ..SizeContext::new(12.0)
+            })
+            .unwrap();
+        assert!((size - 16.4).abs() < 0.1, "{}", size);
+
+        // Without a physical size the value is taken as kitty points
+        let projector = output("DP-2", None);
+        assert_eq!(
+            config.focus_size(&SizeContext {
+                output: Some(&projector),
+                ..SizeContext::new(12.0)
+            }),
+            Some(10.0)
+        );
+        assert_eq!(config.focus_size(&SizeContext::new(12.0)), Some(10.0));
+    }
+
+    #[test]
+    fn test_zoom_config_output_blur_overrides() {
+        let config: ZoomConfig = Figment::new()
+            .merge(Toml::string(
+                "additive = 2.0\n[blur]\nadditive = -1.0\n[output.eDP-1]\nabsolute = 14.0\n[output.eDP-1.blur]\nabsolute = 10.0\n[output.DP-1]\nabsolute = 16.0\n",
+            ))
+            .extract()
+            .unwrap();
+        assert!(config.validate().is_ok());
+
+        let output = |name: &str| OutputInfo {
+            name: name.to_string(),
+            scale: 1.0,
+            logical_size: None,
+            physical_size: None,
+        };
+        let blur_on = |output: Option<&OutputInfo>| {
+            config.blur_size(&SizeContext {
+                output,
+                ..SizeContext::new(12.0)
+            })
+        };
+
+        assert_eq!(blur_on(Some(&output("eDP-1"))), 10.0);
+        assert_eq!(blur_on(Some(&output("DP-1"))), 11.0);
+        assert_eq!(blur_on(None), 11.0);
+
+        let invalid: ZoomConfig = Figment::new()
+            .merge(Toml::string(
+                "[output.eDP-1.blur]\nabsolute = 10.0\nadditive = 1.0\n",
+            ))
+            .extract()
+            .unwrap();
+        let error = invalid.validate().unwrap_err();
+        assert!(
+            error.contains("eDP-1") && error.contains("blur"),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn test_zoom_config_columns_target() {
+        let config = ZoomConfig {
+            columns: Some(100),
+            max_font_size: Some(30.0),
+            ..Default::default()
+        };
+        assert!(config.validate().is_ok());
+        assert_eq!(config.active_type(), Some(ZoomType::Columns));
+
+        // Until the cell width is known the window stays at the baseline
+        assert_eq!(config.focus_size(&SizeContext::new(12.0)), Some(12.0));
+
+        // 1600px wide with 0.8px per column per point: 100 columns at 20pt
+        let ctx = SizeContext {
+            width: Some(1600.0),
+            cell_width: Some(0.8),
+            ..SizeContext::new(12.0)
+        };
+        assert_eq!(config.focus_size(&ctx), Some(20.0));
+
+        // Wider windows need bigger fonts for the same column count
+        let wide = SizeContext {
+            width: Some(3200.0),
+            ..ctx
+        };
+        assert_eq!(config.focus_size(&wide), Some(30.0));
+
+        let zero = ZoomConfig {
+            columns: Some(0),
+            ..Default::default()
+        };
+        assert!(zero.validate().is_err());
+    }
+
+    #[test]
+    fn test_constant_grid_rejects_column_targets() {
+        let config: ZoomConfig = Figment::new()
+            .merge(Toml::string(
+                "multiplicative = 1.5\nconstant_grid = true\n[output.eDP-1]\ncolumns = 100\n",
+            ))
+            .extract()
+            .unwrap();
+        assert!(config.validate().unwrap_err().contains("constant_grid"));
+
+        let config = ZoomConfig {
+            multiplicative: Some(1.5),
+            constant_grid: true,
+            ..Default::default()
+        };
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_zoom_config_rejects_conflicting_focus_targets() {
+        let both = ZoomConfig {
+            additive: Some(6.0),
+            focus: Some(ZoomTarget {
+                absolute: Some(18.0),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert!(both.validate().unwrap_err().contains("not both"));
+
+        let ambiguous_blur = ZoomConfig {
+            blur: Some(ZoomTarget {
+                absolute: Some(10.0),
+                multiplicative: Some(0.8),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert!(
+            ambiguous_blur
+                .validate()
+                .unwrap_err()
+                .starts_with("[zoom.blur]")
+        );
+    }
+
+    #[test]
+    fn test_verbose_flag_raises_verbosity_to_debug() {
+        let mut config = Config {
+            verbose: true,
+            ..Config::default()
+        };
+        assert_eq!(config.effective_verbosity(), Verbosity::Debug);
+
+        config.verbosity = Verbosity::from_count(2);
+        assert_eq!(config.effective_verbosity(), Verbosity::Trace);
+
+        config.verbose = false;
+        config.verbosity = Verbosity::from_count(0);
+        assert_eq!(config.effective_verbosity(), Verbosity::Info);
+    }
 }
@@ -0,0 +1,336 @@
+use super::{Config, Verbosity};
+use figment2::{
+    Figment,
+    providers::{Env, Format, Named, Serialized, Toml},
+};
+use serde::Serialize;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// CLI arguments subset that can override config
+///
+/// `None` means the flag was not passed, so the file, env or default value stands.
+#[derive(Debug, Clone, Default)]
+pub struct CliArgs {
+    pub app_id: Option<String>,
+    pub verbosity: Verbosity,
+    pub journald: bool,
+    pub socket_timeout: Option<u64>,
+    pub max_retries: Option<u32>,
+    pub max_connections: Option<usize>,
+    pub idle_timeout: Option<u64>,
+    pub reap_interval: Option<u64>,
+}
+
+/// CLI arguments subset for zoom configuration
+#[derive(Debug, Clone, Default)]
+pub struct CliZoomArgs {
+    pub absolute: Option<f64>,
+    pub additive: Option<f64>,
+    pub multiplicative: Option<f64>,
+    pub step_size: Option<f64>,
+}
+
+/// Provider names, used to tell the layers apart in `config show`
+const FILE_SOURCE: &str = "config file";
+const ENV_SOURCE: &str = "`ZK_` environment variable(s)";
+const CLI_SOURCE: &str = "CLI flag";
+
+/// The layer a merged config value came from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigSource {
+    Default,
+    File(PathBuf),
+    Env(String),
+    Cli,
+}
+
+impl fmt::Display for ConfigSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Default => write!(f, "default"),
+            Self::File(path) => write!(f, "file {}", path.display()),
+            Self::Env(var) => write!(f, "env {}", var),
+            Self::Cli => write!(f, "CLI"),
+        }
+    }
+}
+
+fn cli<T: Serialize>(key: &str, value: T) -> Named<Serialized<T>> {
+    Named::new(CLI_SOURCE, Serialized::global(key, value))
+}
+
+impl Config {
+    /// Load configuration from multiple sources in order:
+    /// 1. Default values
+    /// 2. Config file at $XDG_CONFIG_HOME/kitty-focus-tracker/config.toml
+    /// 3. Environment variables (ZK_* prefix)
+    /// 4. CLI args (if provided)
+    pub fn load(
+        args: Option<&CliArgs>,
+        zoom_args: Option<&CliZoomArgs>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        Self::from_figment(&Self::figment(args, zoom_args), args, zoom_args)
+    }
+
+    /// Load and validate a single config file, without env or CLI overrides
+    pub fn load_file(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let file = Toml::file(path).search(false).required(true);
+        let figment = Figment::new().merge(Named::new(FILE_SOURCE, file));
+        Self::from_figment(&figment, None, None)
+    }
+
+    /// The layered sources `load` extracts from, without defaults
+    pub fn figment(args: Option<&CliArgs>, zoom_args: Option<&CliZoomArgs>) -> Figment {
+        let mut figment = Figment::new();
+
+        // Add config file if it exists
+        if let Some(config_path) = Self::config_path()
+            && config_path.exists()
+        {
+            figment = figment.merge(Named::new(FILE_SOURCE, Toml::file(config_path)));
+        }
+
+        // Add environment variables with ZK_ prefix
+        figment = figment.merge(Named::new(ENV_SOURCE, Env::prefixed("ZK_").split("__")));
+
+        // Add CLI args that were actually passed
+        if let Some(args) = args {
+            if let Some(app_id) = args.app_id.as_deref().filter(|id| !id.is_empty()) {
+                figment = figment.merge(cli("app_id", app_id));
+            }
+            if args.journald {
+                figment = figment.merge(cli("journald", true));
+            }
+            for (key, value) in [
+                ("socket_timeout_secs", args.socket_timeout),
+                ("idle_timeout_secs", args.idle_timeout),
+                ("reap_interval_secs", args.reap_interval),
+            ] {
+                if let Some(value) = value {
+                    figment = figment.merge(cli(key, value));
+                }
+            }
+            if let Some(max_retries) = args.max_retries {
+                figment = figment.merge(cli("max_retries", max_retries));
+            }
+            if let Some(max_connections) = args.max_connections {
+                figment = figment.merge(cli("max_connections", max_connections));
+            }
+        }
+
+        if let Some(zoom) = zoom_args {
+            for (key, value) in [
+                ("zoom.absolute", zoom.absolute),
+                ("zoom.additive", zoom.additive),
+                ("zoom.multiplicative", zoom.multiplicative),
+                ("zoom.step_size", zoom.step_size),
+            ] {
+                if let Some(value) = value {
+                    figment = figment.merge(cli(key, value));
+                }
+            }
+        }
+
+        figment
+    }
+
+    fn from_figment(
+        figment: &Figment,
+        args: Option<&CliArgs>,
+        zoom_args: Option<&CliZoomArgs>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut config: Config = figment.extract()?;
+
+        if let Some(args) = args {
+            config.verbosity = args.verbosity;
+        }
+
+        // A zoom type passed on the CLI replaces whichever focus type the file or env set
+        if let Some(zoom) = zoom_args {
+            if zoom.absolute.is_some() || zoom.additive.is_some() || zoom.multiplicative.is_some() {
+                config.zoom.focus = None;
+                config.zoom.physical = None;
+                config.zoom.columns = None;
+            }
+            if zoom.multiplicative.is_some() {
+                config.zoom.absolute = None;
+                config.zoom.additive = None;
+            } else if zoom.additive.is_some() {
+                config.zoom.absolute = None;
+                config.zoom.multiplicative = None;
+            } else if zoom.absolute.is_some() {
+                config.zoom.additive = None;
+                config.zoom.multiplicative = None;
+            }
+        }
+
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Which layer of `figment` supplied `key` (dotted, e.g. "zoom.additive")
+    pub fn source_of(figment: &Figment, key: &str) -> ConfigSource {
+        let Some(metadata) = figment.find_metadata(key) else {
+            return ConfigSource::Default;
+        };
+
+        match metadata.name.as_ref() {
+            FILE_SOURCE => metadata
+                .source
+                .as_ref()
+                .and_then(|source| source.file_path())
+                .map_or(ConfigSource::Default, |path| {
+                    ConfigSource::File(path.to_path_buf())
+                }),
+            ENV_SOURCE => ConfigSource::Env(format!(
+                "ZK_{}",
+                key.to_ascii_uppercase().replace('.', "__")
+            )),
+            CLI_SOURCE => ConfigSource::Cli,
+            _ => ConfigSource::Default,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_source_of_reports_each_layer() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(&path, "[zoom]\nadditive = 3.0\n").unwrap();
+
+        let figment = Figment::new()
+            .merge(Named::new(FILE_SOURCE, Toml::file(&path)))
+            .merge(cli("max_retries", 7));
+
+        assert_eq!(
+            Config::source_of(&figment, "zoom.additive"),
+            ConfigSource::File(path)
+        );
+        assert_eq!(
+            Config::source_of(&figment, "max_retries"),
+            ConfigSource::Cli
+        );
+        assert_eq!(Config::source_of(&figment, "app_id"), ConfigSource::Default);
+
+        let config: Config = figment.extract().unwrap();
+        assert_eq!(config.max_retries, 7);
+    }
+
+    /// Run `f` with `Config::config_path` pointing at a config.toml holding `contents`
+    /// and the environment cleared
+    #[allow(clippy::result_large_err)]
+    fn with_config_file(contents: &str, f: impl FnOnce(&mut figment2::Jail)) {
+        figment2::Jail::expect_with(|jail| {
+            jail.clear_env();
+            let dir = jail.directory().to_path_buf();
+            jail.set_env("XDG_CONFIG_HOME", dir.display());
+            jail.create_dir("kitty-focus-tracker")?;
+            jail.create_file("kitty-focus-tracker/config.toml", contents)?;
+            f(jail);
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn test_file_overrides_defaults_when_no_flags_are_passed() {
+        with_config_file(
+            "max_retries = 4\nsocket_timeout_secs = 9\n[zoom]\nadditive = 3.0\nstep_size = 2.0\n",
+            |_| {
+                let config =
+                    Config::load(Some(&CliArgs::default()), Some(&CliZoomArgs::default())).unwrap();
+                assert_eq!(config.app_id, "kitty");
+                assert_eq!(config.max_connections, 30);
+                assert_eq!(config.max_retries, 4);
+                assert_eq!(config.socket_timeout_secs, 9);
+                assert_eq!(config.zoom.additive, Some(3.0));
+                assert_eq!(config.zoom.step_size, 2.0);
+            },
+        );
+    }
+
+    #[test]
+    fn test_env_overrides_file() {
+        with_config_file(
+            "max_retries = 4\nmax_connections = 10\n[zoom]\nadditive = 3.0\n",
+            |jail| {
+                jail.set_env("ZK_MAX_CONNECTIONS", 20);
+                jail.set_env("ZK_ZOOM__ADDITIVE", 5);
+
+                let config = Config::load(Some(&CliArgs::default()), None).unwrap();
+                assert_eq!(config.max_retries, 4);
+                assert_eq!(config.max_connections, 20);
+                assert_eq!(config.zoom.additive, Some(5.0));
+
+                let figment = Config::figment(Some(&CliArgs::default()), None);
+                assert_eq!(
+                    Config::source_of(&figment, "max_connections"),
+                    ConfigSource::Env("ZK_MAX_CONNECTIONS".to_string())
+                );
+            },
+        );
+    }
+
+    #[test]
+    fn test_cli_overrides_env_and_file() {
+        with_config_file(
+            "app_id = \"foot\"\nmax_retries = 4\nidle_timeout_secs = 60\n[zoom]\nadditive = 3.0\nstep_size = 2.0\n",
+            |jail| {
+                jail.set_env("ZK_MAX_CONNECTIONS", 20);
+
+                let args = CliArgs {
+                    max_connections: Some(40),
+                    idle_timeout: Some(90),
+                    ..Default::default()
+                };
+                let zoom_args = CliZoomArgs {
+                    multiplicative: Some(1.5),
+                    ..Default::default()
+                };
+                let config = Config::load(Some(&args), Some(&zoom_args)).unwrap();
+                assert_eq!(config.app_id, "foot");
+                assert_eq!(config.max_retries, 4);
+                assert_eq!(config.max_connections, 40);
+                assert_eq!(config.idle_timeout_secs, 90);
+                assert_eq!(config.zoom.multiplicative, Some(1.5));
+                assert_eq!(config.zoom.additive, None);
+                assert_eq!(config.zoom.step_size, 2.0);
+            },
+        );
+    }
+
+    #[test]
+    fn test_verbosity_flag_does_not_set_verbose() {
+        with_config_file("", |_| {
+            let args = CliArgs {
+                verbosity: Verbosity::from_count(1),
+                ..Default::default()
+            };
+            let config = Config::load(Some(&args), None).unwrap();
+            assert!(!config.verbose);
+            assert_eq!(config.effective_verbosity(), Verbosity::Debug);
+
+            let figment = Config::figment(Some(&args), None);
+            assert_eq!(
+                Config::source_of(&figment, "verbose"),
+                ConfigSource::Default
+            );
+        });
+    }
+
+    #[test]
+    fn test_load_file_validates() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+
+        assert!(Config::load_file(&path).is_err());
+
+        std::fs::write(&path, "[zoom]\nadditive = 3.0\nabsolute = 14.0\n").unwrap();
+        let err = Config::load_file(&path).unwrap_err().to_string();
+        assert!(err.contains("Multiple zoom types"));
+    }
+}
@@ -0,0 +1,347 @@
+use crate::config::ZoomConfig;
+use crate::kitty::KittyRegistry;
+use crate::kitty::resizer::ResizerHandle;
+use crate::kitty::types::KittyInstanceStatus;
+use crate::kitty::util::get_kitty_socket_path;
+use serde::{Deserialize, Serialize};
+use std::io::ErrorKind;
+use std::os::unix::fs::{FileTypeExt, MetadataExt};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{UnixListener, UnixStream};
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+const CONTROL_SOCKET_NAME: &str = "kitty-focus-tracker.sock";
+
+/// Path of the daemon's control socket in the runtime directory
+pub fn control_socket_path() -> PathBuf {
+    let runtime_dir = std::env::var("XDG_RUNTIME_DIR").unwrap_or_else(|_| "/tmp".to_string());
+    PathBuf::from(runtime_dir).join(CONTROL_SOCKET_NAME)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ControlRequest {
+    Status,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "response", rename_all = "snake_case")]
+pub enum ControlResponse {
+    Status(Box<DaemonStatus>),
+    Error { message: String },
+}
+
+/// One kitty instance with the resizer's view of its font size and niri windows
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InstanceStatus {
+    #[serde(flatten)]
+    pub kitty: KittyInstanceStatus,
+    pub font_size: Option<f64>,
+    pub focused: bool,
+    pub niri_windows: Vec<u64>,
+    pub profile: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DaemonStatus {
+    pub pid: u32,
+    pub uptime_secs: u64,
+    pub app_id: String,
+    pub zoom: ZoomConfig,
+    pub baseline_font_size: Option<f64>,
+    pub instances: Vec<InstanceStatus>,
+}
+
+/// Everything the control server needs to answer status requests
+#[derive(Clone)]
+pub struct StatusContext {
+    registry: KittyRegistry,
+    resizer: ResizerHandle,
+    started: Instant,
+}
+
+impl StatusContext {
+    pub fn new(registry: KittyRegistry, resizer: ResizerHandle) -> Self {
+        Self {
+            registry,
+            resizer,
+            started: Instant::now(),
+        }
+    }
+
+    pub async fn status(&self) -> DaemonStatus {
+        let mut instances: Vec<InstanceStatus> = self
+            .registry
+            .instances()
+            .await
+            .into_iter()
+            .map(|kitty| InstanceStatus {
+                kitty,
+                font_size: None,
+                focused: false,
+                niri_windows: Vec::new(),
+                profile: None,
+            })
+            .collect();
+
+        for window in self.resizer.windows() {
+            let kitty_pid = self
+                .registry
+                .cached_kitty_pid(window.pid)
+                .unwrap_or(window.pid);
+
+            let index = match instances
+                .iter()
+                .position(|i| i.kitty.kitty_pid == kitty_pid)
+            {
+                Some(index) => index,
+                None => {
+                    instances.push(InstanceStatus {
+                        kitty: KittyInstanceStatus {
+                            kitty_pid,
+                            socket_path: get_kitty_socket_path(kitty_pid),
+                            status: None,
+                            connected: false,
+                            idle_secs: None,
+                            window_pids: vec![window.pid],
+                        },
+                        font_size: None,
+                        focused: false,
+                        niri_windows: Vec::new(),
+                        profile: None,
+                    });
+                    instances.len() - 1
+                }
+            };
+
+            let instance = &mut instances[index];
+            instance.font_size = window.font_size.or(instance.font_size);
+            instance.focused |= window.focused;
+            instance.profile = window.profile.or(instance.profile.take());
+            instance.niri_windows.extend(window.window_ids);
+            instance.niri_windows.sort_unstable();
+        }
+
+        instances.sort_by_key(|i| i.kitty.kitty_pid);
+
+        let rules = self.resizer.rules();
+        DaemonStatus {
+            pid: std::process::id(),
+            uptime_secs: self.started.elapsed().as_secs(),
+            app_id: rules.app_id().to_string(),
+            zoom: rules.default_zoom().clone(),
+            baseline_font_size: self.resizer.baseline_font_size(),
+            instances,
+        }
+    }
+}
+
+/// The bound control socket; dropping it stops the server and removes the file
+pub struct ControlSocket {
+    path: PathBuf,
+    /// Device and inode of the socket we bound, so a newer daemon's is left alone
+    id: (u64, u64),
+    server: JoinHandle<()>,
+}
+
+impl Drop for ControlSocket {
+    fn drop(&mut self) {
+        self.server.abort();
+        if file_id(&self.path) == Some(self.id)
+            && let Err(e) = std::fs::remove_file(&self.path)
+        {
+            warn!(path = %self.path.display(), error = %e, "Failed to remove control socket");
+        }
+    }
+}
+
+/// Bind the control socket and answer requests in the background
+///
+/// A socket left by a daemon that exited is replaced; if another daemon is still
+/// answering on it, the server is not started and `None` is returned. Anything
+/// else at the path is an error rather than something to delete.
+pub async fn start_server(ctx: StatusContext) -> std::io::Result<Option<ControlSocket>> {
+    serve_at(&control_socket_path(), ctx).await
+}
+
+async fn serve_at(path: &Path, ctx: StatusContext) -> std::io::Result<Option<ControlSocket>> {
+    if let Ok(metadata) = std::fs::symlink_metadata(path) {
+        if !metadata.file_type().is_socket() {
+            return Err(std::io::Error::other(format!(
+                "{} exists and is not a socket",
+                path.display()
+            )));
+        }
+        match UnixStream::connect(path).await {
+            Ok(_) => {
+                warn!(path = %path.display(), "Another daemon owns the control socket, status disabled");
+                return Ok(None);
+            }
+            // Nobody listens: the daemon that bound it is gone
+            Err(e) if e.kind() == ErrorKind::ConnectionRefused => std::fs::remove_file(path)?,
+            Err(e) => return Err(e),
+        }
+    }
+
+    let listener = UnixListener::bind(path)?;
+    let id = file_id(path).ok_or_else(|| std::io::Error::other("control socket vanished"))?;
+    info!(path = %path.display(), "Listening for control requests");
+
+    let server = tokio::spawn(async move {
+        loop {
+            let stream = match listener.accept().await {
+                Ok((stream, _)) => stream,
+                Err(e) => {
+                    warn!(error = %e, "Control socket accept failed");
+                    continue;
+                }
+            };
+
+            let ctx = ctx.clone();
+            tokio::spawn(async move {
+                if let Err(e) = handle_connection(stream, &ctx).await {
+                    debug!(error = %e, "Control request failed");
+                }
+            });
+        }
+    });
+
+    Ok(Some(ControlSocket {
+        path: path.to_path_buf(),
+        id,
+        server,
+    }))
+}
+
+fn file_id(path: &Path) -> Option<(u64, u64)> {
+    let metadata = std::fs::symlink_metadata(path).ok()?;
+    Some((metadata.dev(), metadata.ino()))
+}
+
+async fn handle_connection(stream: UnixStream, ctx: &StatusContext) -> std::io::Result<()> {
+    let (reader, mut writer) = stream.into_split();
+    let mut line = String::new();
+    BufReader::new(reader).read_line(&mut line).await?;
+
+    let response = match serde_json::from_str::<ControlRequest>(line.trim()) {
+        Ok(ControlRequest::Status) => ControlResponse::Status(Box::new(ctx.status().await)),
+        Err(e) => ControlResponse::Error {
+            message: format!("Invalid request: {}", e),
+        },
+    };
+
+    let mut body = serde_json::to_vec(&response)?;
+    body.push(b'\n');
+    writer.write_all(&body).await?;
+    writer.shutdown().await
+}
+
+/// Send a request to the running daemon and wait for its response
+pub async fn send_request(
+    request: &ControlRequest,
+) -> Result<ControlResponse, Box<dyn std::error::Error>> {
+    let path = control_socket_path();
+    let mut stream = UnixStream::connect(&path).await.map_err(|e| {
+        format!(
+            "Could not reach the daemon at {}: {}. Is it running?",
+            path.display(),
+            e
+        )
+    })?;
+
+    let mut body = serde_json::to_vec(request)?;
+    body.push(b'\n');
+    stream.write_all(&body).await?;
+
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await?;
+    Ok(serde_json::from_str(response.trim())?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::RegistryConfig;
+    use crate::kitty::KittyResizer;
+    use crate::kitty::types::KittyConnectionStatus;
+
+    #[test]
+    fn test_status_response_round_trip() {
+        let response = ControlResponse::Status(Box::new(DaemonStatus {
+            pid: 42,
+            uptime_secs: 90,
+            app_id: "kitty".to_string(),
+            zoom: ZoomConfig {
+                additive: Some(6.0),
+                ..Default::default()
+            },
+            baseline_font_size: Some(12.0),
+            instances: vec![InstanceStatus {
+                kitty: KittyInstanceStatus {
+                    kitty_pid: 1234,
+                    socket_path: PathBuf::from("/run/user/1000/kitty-1234.sock"),
+                    status: Some(KittyConnectionStatus::Ready),
+                    connected: true,
+                    idle_secs: Some(3),
+                    window_pids: vec![1234],
+                },
+                font_size: Some(18.0),
+                focused: true,
+                niri_windows: vec![7, 9],
+                profile: Some("default".to_string()),
+            }],
+        }));
+
+        let json = serde_json::to_string(&response).unwrap();
+        assert!(json.contains("\"response\":\"status\""));
+        assert!(json.contains("\"kitty_pid\":1234"));
+
+        match serde_json::from_str::<ControlResponse>(&json).unwrap() {
+            ControlResponse::Status(status) => {
+                assert_eq!(status.instances.len(), 1);
+                let instance = &status.instances[0];
+                assert_eq!(instance.kitty.status, Some(KittyConnectionStatus::Ready));
+                assert_eq!(instance.niri_windows, vec![7, 9]);
+                assert_eq!(status.zoom.additive, Some(6.0));
+            }
+            other => panic!("unexpected response: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_request_wire_format() {
+        let json = serde_json::to_string(&ControlRequest::Status).unwrap();
+        assert_eq!(json, "\"status\"");
+    }
+
+    fn context() -> StatusContext {
+        let registry = KittyRegistry::new(RegistryConfig::default());
+        let resizer = KittyResizer::new(registry.clone());
+        StatusContext::new(registry, resizer.handle())
+    }
+
+    #[tokio::test]
+    async fn test_control_socket_replaces_only_dead_sockets_and_is_removed_on_drop() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("control.sock");
+
+        // Left behind by a daemon that exited without cleaning up
+        drop(std::os::unix::net::UnixListener::bind(&path).unwrap());
+        let socket = serve_at(&path, context()).await.unwrap().unwrap();
+        assert!(UnixStream::connect(&path).await.is_ok());
+
+        // A second daemon leaves the live socket alone
+        assert!(serve_at(&path, context()).await.unwrap().is_none());
+        assert!(path.exists());
+
+        drop(socket);
+        assert!(!path.exists());
+
+        std::fs::write(&path, "not a socket").unwrap();
+        assert!(serve_at(&path, context()).await.is_err());
+        assert!(path.exists());
+    }
+}
@@ -0,0 +1,729 @@
+use crate::effects::{EffectResult, EffectsConfig, FocusEffect};
+use crate::kitty::ZoomingResult;
+use crate::kitty::appearance::AppearanceController;
+use crate::kitty::colors::{Colors, desaturate, load_theme};
+use crate::kitty::conf_parser::get_background_opacity;
+use crate::niri::types::WindowInfo;
+use crate::rules::ZoomProfile;
+use dashmap::DashMap;
+use futures::future::BoxFuture;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+use tracing::{debug, warn};
+
+fn default_dim_opacity() -> f64 {
+    0.8
+}
+
+/// `[effect.dim]`: lower the background opacity of unfocused kitty windows
+///
+/// kitty only takes opacity changes with `dynamic_background_opacity yes`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct DimConfig {
+    /// Background opacity of unfocused windows, from 0.0 (clear) to 1.0 (opaque)
+    #[serde(default = "default_dim_opacity")]
+    pub opacity: f64,
+
+    /// Background opacity of the focused window; kitty.conf's `background_opacity`
+    /// when unset
+    #[serde(default)]
+    pub focused_opacity: Option<f64>,
+}
+
+impl DimConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        for (name, opacity) in [
+            ("opacity", Some(self.opacity)),
+            ("focused_opacity", self.focused_opacity),
+        ] {
+            if let Some(opacity) = opacity
+                && !(0.0..=1.0).contains(&opacity)
+            {
+                return Err(format!(
+                    "{} must be between 0.0 and 1.0, got {}",
+                    name, opacity
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Opacity for the focused window
+    pub fn focused(&self) -> f64 {
+        self.focused_opacity.unwrap_or_else(get_background_opacity)
+    }
+}
+
+/// `[effect.colors]`: mute the colors of unfocused kitty windows
+///
+/// Unfocused windows get either the `blur` theme or their own configured colors
+/// desaturated; focused windows get the `focus` theme or their configured colors back.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct ColorsConfig {
+    /// kitty theme file (.conf) for unfocused windows
+    pub blur: Option<PathBuf>,
+
+    /// kitty theme file for the focused window; kitty.conf's colors when unset
+    pub focus: Option<PathBuf>,
+
+    /// Instead of a `blur` theme, fade unfocused windows' colors towards gray by
+    /// this much, from 0.0 (unchanged) to 1.0 (grayscale)
+    pub desaturate: Option<f64>,
+}
+
+/// Unfocused colors of `[effect.colors]`
+#[derive(Debug, Clone, PartialEq)]
+pub enum MutedColors {
+    Theme(Colors),
+    Desaturate(f64),
+}
+
+/// `[effect.colors]` with its theme files loaded
+#[derive(Debug, Clone, PartialEq)]
+pub struct Themes {
+    pub blur: MutedColors,
+    pub focus: Option<Colors>,
+}
+
+impl ColorsConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        self.load().map(|_| ())
+    }
+
+    /// Read the theme files
+    pub fn load(&self) -> Result<Themes, String> {
+        let blur = match (&self.blur, self.desaturate) {
+            (Some(_), Some(_)) => return Err("set either blur or desaturate, not both".to_string()),
+            (None, None) => return Err("set a blur theme or desaturate".to_string()),
+            (Some(path), None) => MutedColors::Theme(load_theme(path)?),
+            (None, Some(amount)) if (0.0..=1.0).contains(&amount) => {
+                MutedColors::Desaturate(amount)
+            }
+            (None, Some(amount)) => {
+                return Err(format!(
+                    "desaturate must be between 0.0 and 1.0, got {}",
+                    amount
+                ));
+            }
+        };
+        let focus = self.focus.as_deref().map(load_theme).transpose()?;
+
+        Ok(Themes { blur, focus })
+    }
+}
+
+/// `[zoom.spacing]`: kitty window padding and margin while a window has focus
+///
+/// Unfocused windows get kitty.conf's `window_padding_width` and
+/// `window_margin_width` back. Unset values also keep kitty.conf's.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct SpacingConfig {
+    /// Padding around the text of the focused window, in points
+    pub padding: Option<f64>,
+
+    /// Margin around the focused window, in points
+    pub margin: Option<f64>,
+}
+
+impl SpacingConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.padding.is_none() && self.margin.is_none() {
+            return Err("set padding, margin or both".to_string());
+        }
+        for (name, value) in [("padding", self.padding), ("margin", self.margin)] {
+            if let Some(value) = value
+                && (value < 0.0 || value.is_nan())
+            {
+                return Err(format!("{} must not be negative, got {}", name, value));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// `[effect.dim]`: background opacity by focus
+pub struct DimEffect {
+    kitty: Arc<dyn AppearanceController>,
+    config: RwLock<Option<DimConfig>>,
+    /// Background opacity kitty last confirmed per window
+    opacity: DashMap<i32, f64>,
+}
+
+impl DimEffect {
+    pub fn new(kitty: Arc<dyn AppearanceController>) -> Self {
+        Self {
+            kitty,
+            config: RwLock::new(None),
+            opacity: DashMap::new(),
+        }
+    }
+
+    /// Send the opacity for a window gaining or losing focus, unless kitty already has it
+    async fn apply(&self, pid: i32, focused: bool) -> EffectResult {
+        let Some(dim) = self.config.read().unwrap().clone() else {
+            return Ok(());
+        };
+        let opacity = if focused { dim.focused() } else { dim.opacity };
+        if self
+            .opacity
+            .get(&pid)
+            .is_some_and(|current| *current == opacity)
+        {
+            return Ok(());
+        }
+
+        self.set(pid, opacity).await
+    }
+
+    async fn set(&self, pid: i32, opacity: f64) -> EffectResult {
+        match self.kitty.set_background_opacity(pid, opacity).await {
+            Ok(ZoomingResult::Success { .. }) => {
+                debug!(pid, opacity, "Set background opacity");
+                self.opacity.insert(pid, opacity);
+                Ok(())
+            }
+            Ok(result) => {
+                debug!(pid, result = result.label(), "Background opacity unchanged");
+                Ok(())
+            }
+            Err(e) => Err(format!("Failed to set background opacity: {}", e)),
+        }
+    }
+}
+
+impl FocusEffect for DimEffect {
+    fn name(&self) -> &'static str {
+        "dim"
+    }
+
+    fn configure(&self, effects: &EffectsConfig) {
+        *self.config.write().unwrap() = effects.dim.clone();
+    }
+
+    fn on_focus<'a>(
+        &'a self,
+        _window: &'a WindowInfo,
+        pid: i32,
+        _profile: &'a ZoomProfile,
+    ) -> BoxFuture<'a, EffectResult> {
+        Box::pin(self.apply(pid, true))
+    }
+
+    fn on_blur<'a>(
+        &'a self,
+        _window: &'a WindowInfo,
+        pid: i32,
+        profile: Option<&'a ZoomProfile>,
+    ) -> BoxFuture<'a, EffectResult> {
+        Box::pin(async move {
+            match profile {
+                Some(_) => self.apply(pid, false).await,
+                None => Ok(()),
+            }
+        })
+    }
+
+    fn on_destroy(&self, pid: i32) {
+        self.opacity.remove(&pid);
+    }
+
+    /// Removing `[effect.dim]` on reload gives every dimmed window its opacity back
+    fn restore(&self, everything: bool) -> BoxFuture<'_, EffectResult> {
+        Box::pin(async move {
+            if !everything && self.config.read().unwrap().is_some() {
+                return Ok(());
+            }
+
+            let pids: Vec<i32> = self.opacity.iter().map(|entry| *entry.key()).collect();
+            let mut result = Ok(());
+            for pid in pids {
+                result = result.and(self.set(pid, get_background_opacity()).await);
+                self.opacity.remove(&pid);
+            }
+            result
+        })
+    }
+}
+
+/// Colors the colors effect gave a window
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Palette {
+    Muted,
+    Focused,
+}
+
+/// `[effect.colors]`: muted colors for unfocused windows
+pub struct ColorsEffect {
+    kitty: Arc<dyn AppearanceController>,
+    /// `[effect.colors]` with its themes loaded
+    themes: RwLock<Option<Arc<Themes>>>,
+    /// Palette kitty last confirmed per window; absent is kitty.conf's
+    palettes: DashMap<i32, Palette>,
+}
+
+impl ColorsEffect {
+    pub fn new(kitty: Arc<dyn AppearanceController>) -> Self {
+        Self {
+            kitty,
+            themes: RwLock::new(None),
+            palettes: DashMap::new(),
+        }
+    }
+
+    /// Send the palette for a window gaining or losing focus, unless kitty already has it
+    async fn apply(&self, pid: i32, focused: bool) -> EffectResult {
+        let Some(themes) = self.themes.read().unwrap().clone() else {
+            return Ok(());
+        };
+        let wanted = if focused {
+            themes.focus.as_ref().map(|_| Palette::Focused)
+        } else {
+            Some(Palette::Muted)
+        };
+        if self.palettes.get(&pid).map(|palette| *palette) == wanted {
+            return Ok(());
+        }
+
+        let colors = match wanted {
+            None => None,
+            Some(Palette::Focused) => themes.focus.clone(),
+            Some(Palette::Muted) => match &themes.blur {
+                MutedColors::Theme(theme) => Some(theme.clone()),
+                MutedColors::Desaturate(amount) => {
+                    let Some(configured) = self.kitty.configured_colors(pid).await else {
+                        return Err("Could not read kitty's colors to desaturate".to_string());
+                    };
+                    Some(desaturate(&configured, *amount))
+                }
+            },
+        };
+
+        self.set(pid, wanted, colors.as_ref()).await
+    }
+
+    /// Send `colors`, or reset to kitty.conf's colors when `None`, and record
+    /// `palette` once kitty confirms
+    async fn set(
+        &self,
+        pid: i32,
+        palette: Option<Palette>,
+        colors: Option<&Colors>,
+    ) -> EffectResult {
+        let result = match colors {
+            Some(colors) => self.kitty.set_colors(pid, colors).await,
+            None => Ok(self.kitty.reset_colors(pid).await),
+        };
+        match result {
+            Ok(ZoomingResult::Success { .. }) => {
+                debug!(pid, ?palette, "Set colors");
+                match palette {
+                    Some(palette) => self.palettes.insert(pid, palette),
+                    None => self.palettes.remove(&pid).map(|(_, palette)| palette),
+                };
+                Ok(())
+            }
+            Ok(result) => {
+                debug!(pid, result = result.label(), "Colors unchanged");
+                Ok(())
+            }
+            Err(e) => Err(format!("Failed to set colors: {}", e)),
+        }
+    }
+}
+
+impl FocusEffect for ColorsEffect {
+    fn name(&self) -> &'static str {
+        "colors"
+    }
+
+    fn configure(&self, effects: &EffectsConfig) {
+        let themes = effects.colors.as_ref().and_then(|colors| {
+            colors
+                .load()
+                .map_err(|e| warn!(error = %e, "Not applying [effect.colors]"))
+                .ok()
+        });
+        *self.themes.write().unwrap() = themes.map(Arc::new);
+    }
+
+    fn on_focus<'a>(
+        &'a self,
+        _window: &'a WindowInfo,
+        pid: i32,
+        _profile: &'a ZoomProfile,
+    ) -> BoxFuture<'a, EffectResult> {
+        Box::pin(self.apply(pid, true))
+    }
+
+    fn on_blur<'a>(
+        &'a self,
+        _window: &'a WindowInfo,
+        pid: i32,
+        profile: Option<&'a ZoomProfile>,
+    ) -> BoxFuture<'a, EffectResult> {
+        Box::pin(async move {
+            match profile {
+                Some(_) => self.apply(pid, false).await,
+                None => Ok(()),
+            }
+        })
+    }
+
+    fn on_destroy(&self, pid: i32) {
+        self.palettes.remove(&pid);
+    }
+
+    fn restore(&self, everything: bool) -> BoxFuture<'_, EffectResult> {
+        Box::pin(async move {
+            if !everything && self.themes.read().unwrap().is_some() {
+                return Ok(());
+            }
+
+            let pids: Vec<i32> = self.palettes.iter().map(|entry| *entry.key()).collect();
+            let mut result = Ok(());
+            for pid in pids {
+                result = result.and(self.set(pid, None, None).await);
+            }
+            result
+        })
+    }
+}
+
+/// `[zoom.spacing]`: the focused window's profile padding and margin
+pub struct SpacingEffect {
+    kitty: Arc<dyn AppearanceController>,
+    /// Spacing kitty last confirmed per window; absent is kitty.conf's
+    spacing: DashMap<i32, SpacingConfig>,
+}
+
+impl SpacingEffect {
+    pub fn new(kitty: Arc<dyn AppearanceController>) -> Self {
+        Self {
+            kitty,
+            spacing: DashMap::new(),
+        }
+    }
+
+    /// Send `spacing`, or kitty.conf's spacing for `None`, unless kitty already has it
+    async fn apply(&self, pid: i32, spacing: Option<&SpacingConfig>) -> EffectResult {
+        let current = self.spacing.get(&pid).map(|current| current.clone());
+        if current.as_ref() == spacing {
+            return Ok(());
+        }
+
+        let (padding, margin) = spacing.map_or((None, None), |s| (s.padding, s.margin));
+        match self.kitty.set_spacing(pid, padding, margin).await {
+            Ok(ZoomingResult::Success { .. }) => {
+                debug!(pid, ?padding, ?margin, "Set spacing");
+                match spacing {
+                    Some(spacing) => self.spacing.insert(pid, spacing.clone()),
+                    None => self.spacing.remove(&pid).map(|(_, spacing)| spacing),
+                };
+                Ok(())
+            }
+            Ok(result) => {
+                debug!(pid, result = result.label(), "Spacing unchanged");
+                Ok(())
+            }
+            Err(e) => Err(format!("Failed to set spacing: {}", e)),
+        }
+    }
+}
+
+impl FocusEffect for SpacingEffect {
+    fn name(&self) -> &'static str {
+        "spacing"
+    }
+
+    fn on_focus<'a>(
+        &'a self,
+        _window: &'a WindowInfo,
+        pid: i32,
+        profile: &'a ZoomProfile,
+    ) -> BoxFuture<'a, EffectResult> {
+        Box::pin(self.apply(pid, profile.zoom.spacing.as_ref()))
+    }
+
+    fn on_blur<'a>(
+        &'a self,
+        _window: &'a WindowInfo,
+        pid: i32,
+        _profile: Option<&'a ZoomProfile>,
+    ) -> BoxFuture<'a, EffectResult> {
+        Box::pin(self.apply(pid, None))
+    }
+
+    fn on_destroy(&self, pid: i32) {
+        self.spacing.remove(&pid);
+    }
+
+    /// Spacing follows the focused window's profile, so only `everything` resets it
+    fn restore(&self, everything: bool) -> BoxFuture<'_, EffectResult> {
+        Box::pin(async move {
+            if !everything {
+                return Ok(());
+            }
+
+            let pids: Vec<i32> = self.spacing.iter().map(|entry| *entry.key()).collect();
+            let mut result = Ok(());
+            for pid in pids {
+                result = result.and(self.apply(pid, None).await);
+            }
+            result
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Config, ZoomConfig};
+    use crate::kitty::appearance::FakeAppearance;
+    use figment2::Figment;
+    use figment2::providers::{Format, Toml};
+
+    fn window() -> WindowInfo {
+        WindowInfo {
+            id: 1,
+            app_id: Some("kitty".to_string()),
+            pid: Some(100),
+            ..Default::default()
+        }
+    }
+
+    fn profile(zoom: ZoomConfig) -> ZoomProfile {
+        ZoomProfile {
+            name: Arc::from("default"),
+            zoom,
+        }
+    }
+
+    fn effects(config: &str) -> EffectsConfig {
+        let config: Config = Figment::new()
+            .merge(Toml::string(config))
+            .extract()
+            .unwrap();
+        config.effect
+    }
+
+    #[tokio::test]
+    async fn test_dim_follows_focus_once_per_window() {
+        let kitty = Arc::new(FakeAppearance::new());
+        let dim = DimEffect::new(kitty.clone());
+        dim.configure(&effects(
+            "[effect.dim]\nopacity = 0.6\nfocused_opacity = 0.95\n",
+        ));
+        let (window, profile) = (window(), profile(ZoomConfig::default()));
+
+        dim.on_blur(&window, 100, Some(&profile)).await.unwrap();
+        assert_eq!(kitty.opacity(100), Some(0.6));
+        dim.on_blur(&window, 100, Some(&profile)).await.unwrap();
+        assert_eq!(kitty.sent(100).len(), 1);
+
+        dim.on_focus(&window, 100, &profile).await.unwrap();
+        dim.on_focus(&window, 100, &profile).await.unwrap();
+        assert_eq!(kitty.opacity(100), Some(0.95));
+        assert_eq!(kitty.sent(100).len(), 2);
+
+        // Each window has its own opacity
+        dim.on_blur(&window, 200, Some(&profile)).await.unwrap();
+        assert_eq!(kitty.opacity(200), Some(0.6));
+        assert_eq!(kitty.opacity(100), Some(0.95));
+
+        // Windows no rule zooms are left alone
+        dim.on_blur(&window, 300, None).await.unwrap();
+        assert!(kitty.sent(300).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_dim_restore() {
+        let kitty = Arc::new(FakeAppearance::new());
+        let dim = DimEffect::new(kitty.clone());
+        dim.configure(&effects("[effect.dim]\nfocused_opacity = 0.95\n"));
+        let (window, profile) = (window(), profile(ZoomConfig::default()));
+        dim.on_blur(&window, 100, Some(&profile)).await.unwrap();
+        dim.on_focus(&window, 200, &profile).await.unwrap();
+
+        // Still configured: nothing to undo
+        dim.restore(false).await.unwrap();
+        assert_eq!(kitty.opacity(100), Some(0.8));
+
+        dim.restore(true).await.unwrap();
+        assert_eq!(kitty.opacity(100), Some(get_background_opacity()));
+        assert_eq!(kitty.opacity(200), Some(get_background_opacity()));
+        assert!(dim.opacity.is_empty());
+    }
+
+    #[test]
+    fn test_dim_config_defaults_and_limits() {
+        let config: Config = Figment::new()
+            .merge(Toml::string("[effect.dim]\nfocused_opacity = 0.95\n"))
+            .extract()
+            .unwrap();
+        let dim = config.effect.dim.unwrap();
+        assert_eq!(dim.opacity, 0.8);
+        assert_eq!(dim.focused(), 0.95);
+
+        let dim = DimConfig {
+            opacity: 1.2,
+            focused_opacity: None,
+        };
+        assert!(dim.validate().unwrap_err().contains("opacity"));
+    }
+
+    fn colors(background: u64) -> Colors {
+        Colors::from_iter([("background".to_string(), background.into())])
+    }
+
+    fn colors_effect(kitty: &Arc<FakeAppearance>, themes: Themes) -> ColorsEffect {
+        let effect = ColorsEffect::new(kitty.clone());
+        *effect.themes.write().unwrap() = Some(Arc::new(themes));
+        effect
+    }
+
+    #[tokio::test]
+    async fn test_colors_swap_on_focus_and_blur() {
+        let kitty = Arc::new(FakeAppearance::new());
+        let effect = colors_effect(
+            &kitty,
+            Themes {
+                blur: MutedColors::Theme(colors(0x101010)),
+                focus: Some(colors(0x202020)),
+            },
+        );
+        let (window, profile) = (window(), profile(ZoomConfig::default()));
+
+        effect.on_blur(&window, 100, Some(&profile)).await.unwrap();
+        assert_eq!(kitty.colors(100), Some(colors(0x101010)));
+        effect.on_focus(&window, 100, &profile).await.unwrap();
+        assert_eq!(kitty.colors(100), Some(colors(0x202020)));
+        effect.on_focus(&window, 100, &profile).await.unwrap();
+        assert_eq!(kitty.sent(100).len(), 2);
+
+        // Without a focus theme, focus goes back to kitty.conf's colors
+        let kitty = Arc::new(FakeAppearance::new().with_configured_colors(colors(0xff0000)));
+        let effect = colors_effect(
+            &kitty,
+            Themes {
+                blur: MutedColors::Desaturate(0.5),
+                focus: None,
+            },
+        );
+        effect.on_blur(&window, 100, Some(&profile)).await.unwrap();
+        assert_eq!(kitty.colors(100), Some(desaturate(&colors(0xff0000), 0.5)));
+        effect.on_focus(&window, 100, &profile).await.unwrap();
+        assert_eq!(kitty.colors(100), None);
+        assert_eq!(kitty.sent(100), ["set-colors", "set-colors --reset"]);
+    }
+
+    #[tokio::test]
+    async fn test_colors_restore() {
+        let kitty = Arc::new(FakeAppearance::new());
+        let effect = colors_effect(
+            &kitty,
+            Themes {
+                blur: MutedColors::Theme(colors(0x101010)),
+                focus: Some(colors(0x202020)),
+            },
+        );
+        let (window, profile) = (window(), profile(ZoomConfig::default()));
+        effect.on_blur(&window, 100, Some(&profile)).await.unwrap();
+        effect.on_focus(&window, 200, &profile).await.unwrap();
+
+        effect.restore(false).await.unwrap();
+        assert_eq!(kitty.colors(100), Some(colors(0x101010)));
+
+        // Removing [effect.colors] on reload resets every window it colored
+        effect.configure(&EffectsConfig::default());
+        effect.restore(false).await.unwrap();
+        assert_eq!(kitty.colors(100), None);
+        assert_eq!(kitty.colors(200), None);
+        assert!(effect.palettes.is_empty());
+    }
+
+    #[test]
+    fn test_colors_config_loads_themes() {
+        let dir = tempfile::tempdir().unwrap();
+        let muted = dir.path().join("muted.conf");
+        std::fs::write(
+            &muted,
+            "foreground #808080
+background #101010
+",
+        )
+        .unwrap();
+
+        let colors = ColorsConfig {
+            blur: Some(muted),
+            ..Default::default()
+        };
+        let MutedColors::Theme(theme) = colors.load().unwrap().blur else {
+            panic!("expected a theme");
+        };
+        assert_eq!(theme["background"], 0x101010);
+
+        let colors = ColorsConfig {
+            desaturate: Some(0.6),
+            focus: Some(dir.path().join("missing.conf")),
+            ..Default::default()
+        };
+        assert!(colors.validate().is_err());
+        assert!(ColorsConfig::default().validate().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_spacing_follows_the_profile() {
+        let kitty = Arc::new(FakeAppearance::new());
+        let spacing = SpacingEffect::new(kitty.clone());
+        let window = window();
+        let roomy = profile(ZoomConfig {
+            spacing: Some(SpacingConfig {
+                padding: Some(20.0),
+                margin: Some(4.0),
+            }),
+            ..Default::default()
+        });
+        let plain = profile(ZoomConfig::default());
+
+        spacing.on_focus(&window, 100, &roomy).await.unwrap();
+        spacing.on_focus(&window, 100, &roomy).await.unwrap();
+        assert_eq!(kitty.spacing(100), (Some(20.0), Some(4.0)));
+        assert_eq!(kitty.sent(100).len(), 1);
+
+        spacing.on_blur(&window, 100, Some(&roomy)).await.unwrap();
+        assert_eq!(kitty.spacing(100), (None, None));
+
+        // A profile without spacing leaves kitty.conf's alone
+        spacing.on_focus(&window, 200, &plain).await.unwrap();
+        assert!(kitty.sent(200).is_empty());
+
+        spacing.on_focus(&window, 100, &roomy).await.unwrap();
+        spacing.on_focus(&window, 200, &roomy).await.unwrap();
+        spacing.restore(false).await.unwrap();
+        assert_eq!(kitty.spacing(100), (Some(20.0), Some(4.0)));
+        spacing.restore(true).await.unwrap();
+        assert_eq!(kitty.spacing(100), (None, None));
+        assert_eq!(kitty.spacing(200), (None, None));
+        assert!(spacing.spacing.is_empty());
+    }
+
+    #[test]
+    fn test_spacing_config_validation() {
+        let spacing = SpacingConfig {
+            padding: Some(20.0),
+            margin: None,
+        };
+        assert!(spacing.validate().is_ok());
+
+        assert!(SpacingConfig::default().validate().is_err());
+        let spacing = SpacingConfig {
+            padding: None,
+            margin: Some(-1.0),
+        };
+        assert!(spacing.validate().unwrap_err().contains("margin"));
+    }
+}
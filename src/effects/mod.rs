@@ -0,0 +1,83 @@
+pub mod kitty;
+pub mod niri;
+
+use futures::future::BoxFuture;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use self::kitty::{ColorsConfig, DimConfig};
+use self::niri::NiriEffect;
+use crate::niri::types::WindowInfo;
+use crate::rules::ZoomProfile;
+
+/// Outcome of one effect on one window; errors are logged with the effect's name
+pub type EffectResult = Result<(), String>;
+
+/// Something `KittyResizer` does to a tracked kitty window as it gains and loses focus
+///
+/// The resizer runs its effects in the order they were added, for every event,
+/// and logs a failing effect without skipping the ones after it. Each effect
+/// keeps its own per-window state and takes its own part of `[effect]`.
+pub trait FocusEffect: Send + Sync {
+    /// Name used in logs
+    fn name(&self) -> &'static str;
+
+    /// Take the effect's settings from a loaded or reloaded `[effect]`
+    fn configure(&self, _effects: &EffectsConfig) {}
+
+    /// `window` of kitty `pid` gained focus and `profile` zooms it
+    fn on_focus<'a>(
+        &'a self,
+        window: &'a WindowInfo,
+        pid: i32,
+        profile: &'a ZoomProfile,
+    ) -> BoxFuture<'a, EffectResult>;
+
+    /// `window` of kitty `pid` lost focus; `profile` is what zooms it now, if anything
+    fn on_blur<'a>(
+        &'a self,
+        window: &'a WindowInfo,
+        pid: i32,
+        profile: Option<&'a ZoomProfile>,
+    ) -> BoxFuture<'a, EffectResult>;
+
+    /// The last tracked window of kitty `pid` closed; forget about it
+    fn on_destroy(&self, pid: i32);
+
+    /// Give windows back what kitty.conf sets for changes no longer configured,
+    /// or for everything the effect changed when `everything`
+    fn restore(&self, everything: bool) -> BoxFuture<'_, EffectResult>;
+}
+
+/// `[effect]` tables: what else happens when tracked windows gain or lose focus
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct EffectsConfig {
+    /// niri layout actions (`[[effect.niri]]`), performed in order
+    pub niri: Vec<NiriEffect>,
+
+    /// Dim unfocused kitty windows (`[effect.dim]`)
+    pub dim: Option<DimConfig>,
+
+    /// Mute the colors of unfocused kitty windows (`[effect.colors]`)
+    pub colors: Option<ColorsConfig>,
+}
+
+impl EffectsConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        for (i, effect) in self.niri.iter().enumerate() {
+            effect
+                .validate()
+                .map_err(|e| format!("[[effect.niri]] #{}: {}", i + 1, e))?;
+        }
+        if let Some(dim) = &self.dim {
+            dim.validate().map_err(|e| format!("[effect.dim]: {}", e))?;
+        }
+        if let Some(colors) = &self.colors {
+            colors
+                .validate()
+                .map_err(|e| format!("[effect.colors]: {}", e))?;
+        }
+        Ok(())
+    }
+}
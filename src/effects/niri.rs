@@ -0,0 +1,333 @@
+use crate::config::Config;
+use crate::handlers::{EventHandler, EventKind, HandlerResult};
+use crate::niri::actions::NiriActions;
+use crate::niri::types::{NiriEvent, WindowInfo};
+use crate::rules::Rules;
+use futures::future::BoxFuture;
+use niri_ipc::{Action, SizeChange};
+use schemars::{JsonSchema, Schema, SchemaGenerator, json_schema};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::borrow::Cow;
+use tokio::sync::watch;
+use tracing::{debug, info, warn};
+
+/// A width or height change written as niri's CLI takes it: "800", "50%", "+10%", "-100"
+#[derive(Debug, Clone)]
+pub struct Size {
+    source: String,
+    change: SizeChange,
+}
+
+impl Size {
+    pub fn new(size: &str) -> Result<Self, String> {
+        let change = size
+            .parse()
+            .map_err(|e| format!("invalid size '{}': {}", size, e))?;
+        Ok(Self {
+            source: size.to_string(),
+            change,
+        })
+    }
+
+    pub fn change(&self) -> SizeChange {
+        self.change
+    }
+}
+
+impl Serialize for Size {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.source)
+    }
+}
+
+impl<'de> Deserialize<'de> for Size {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let size = String::deserialize(deserializer)?;
+        Self::new(&size).map_err(serde::de::Error::custom)
+    }
+}
+
+impl JsonSchema for Size {
+    fn schema_name() -> Cow<'static, str> {
+        "Size".into()
+    }
+
+    fn inline_schema() -> bool {
+        true
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        json_schema!({
+            "type": "string",
+            "pattern": "^[+-]?[0-9]+(\\.[0-9]+)?%?$"
+        })
+    }
+}
+
+/// A niri layout action, written as `{ action = "center-column" }`
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "action", rename_all = "kebab-case")]
+pub enum NiriAction {
+    /// Set the width of the window's column, e.g. `width = "66%"`
+    SetColumnWidth { width: Size },
+    /// Set the window's height within its column, e.g. `height = "50%"`
+    SetWindowHeight { height: Size },
+    /// Give the window its automatic height back
+    ResetWindowHeight,
+    /// Cycle the window's column through niri's preset widths
+    SwitchPresetColumnWidth,
+    /// Center the window's column on screen
+    CenterWindow,
+    /// Center the focused column on screen
+    CenterColumn,
+    /// Widen the focused column to fill the free space on screen
+    ExpandColumnToAvailableWidth,
+    /// Toggle the focused column between full width and its previous width
+    MaximizeColumn,
+}
+
+impl NiriAction {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::SetColumnWidth { .. } => "set-column-width",
+            Self::SetWindowHeight { .. } => "set-window-height",
+            Self::ResetWindowHeight => "reset-window-height",
+            Self::SwitchPresetColumnWidth => "switch-preset-column-width",
+            Self::CenterWindow => "center-window",
+            Self::CenterColumn => "center-column",
+            Self::ExpandColumnToAvailableWidth => "expand-column-to-available-width",
+            Self::MaximizeColumn => "maximize-column",
+        }
+    }
+
+    /// Whether niri can apply the action to a given window, rather than only to
+    /// the focused column
+    pub fn targets_window(&self) -> bool {
+        !matches!(
+            self,
+            Self::CenterColumn | Self::ExpandColumnToAvailableWidth | Self::MaximizeColumn
+        )
+    }
+
+    /// The IPC action for `window_id`; column actions go to the focused column
+    pub fn to_action(&self, window_id: u64) -> Action {
+        let id = Some(window_id);
+        match self {
+            Self::SetColumnWidth { width } => Action::SetWindowWidth {
+                id,
+                change: width.change(),
+            },
+            Self::SetWindowHeight { height } => Action::SetWindowHeight {
+                id,
+                change: height.change(),
+            },
+            Self::ResetWindowHeight => Action::ResetWindowHeight { id },
+            Self::SwitchPresetColumnWidth => Action::SwitchPresetWindowWidth { id },
+            Self::CenterWindow => Action::CenterWindow { id },
+            Self::CenterColumn => Action::CenterColumn {},
+            Self::ExpandColumnToAvailableWidth => Action::ExpandColumnToAvailableWidth {},
+            Self::MaximizeColumn => Action::MaximizeColumn {},
+        }
+    }
+}
+
+/// One `[[effect.niri]]` table: actions for tracked windows gaining and losing focus
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct NiriEffect {
+    /// Only windows zoomed by the rule with this name ("default" for `[zoom]`);
+    /// every tracked window when unset
+    pub rule: Option<String>,
+
+    /// Performed when a tracked window gains focus
+    pub focus: Option<NiriAction>,
+
+    /// Performed on a tracked window when it loses focus
+    pub blur: Option<NiriAction>,
+}
+
+impl NiriEffect {
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(blur) = &self.blur
+            && !blur.targets_window()
+        {
+            return Err(format!(
+                "blur action '{}' acts on the focused column, which is no longer this window",
+                blur.name()
+            ));
+        }
+        Ok(())
+    }
+
+    fn applies_to(&self, profile: &str) -> bool {
+        self.rule.as_deref().is_none_or(|rule| rule == profile)
+    }
+}
+
+/// Performs `[[effect.niri]]` actions as tracked windows gain and lose focus
+///
+/// Runs as the `niri-effects` event handler beside `KittyResizer`, so slow niri
+/// replies never hold up font changes. Reloaded configs apply from the next event.
+pub struct NiriEffects {
+    effects: Vec<NiriEffect>,
+    rules: Rules,
+    actions: NiriActions,
+    config_updates: Option<watch::Receiver<Config>>,
+    focused: Option<u64>,
+}
+
+impl NiriEffects {
+    pub const NAME: &'static str = "niri-effects";
+
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            effects: config.effect.niri.clone(),
+            rules: Rules::from_config(config),
+            actions: NiriActions::new(),
+            config_updates: None,
+            focused: None,
+        }
+    }
+
+    /// Swap in effects and rules from configs published on `updates`
+    pub fn with_config_updates(mut self, updates: watch::Receiver<Config>) -> Self {
+        self.config_updates = Some(updates);
+        self
+    }
+
+    /// Take effects and rules from the latest config published on the updates channel
+    fn apply_updates(&mut self) {
+        let Some(updates) = &mut self.config_updates else {
+            return;
+        };
+        if !updates.has_changed().unwrap_or(false) {
+            return;
+        }
+        let config = updates.borrow_and_update().clone();
+        self.effects = config.effect.niri.clone();
+        self.rules = Rules::from_config(&config);
+        info!(
+            effects = self.effects.len(),
+            "Applied reloaded niri effects"
+        );
+    }
+
+    async fn perform<F>(&self, window: &WindowInfo, pick: F)
+    where
+        F: Fn(&NiriEffect) -> Option<&NiriAction>,
+    {
+        let Some(profile) = self.rules.resolve(window) else {
+            return;
+        };
+
+        let actions = self
+            .effects
+            .iter()
+            .filter(|effect| effect.applies_to(&profile.name))
+            .filter_map(pick);
+        for action in actions {
+            debug!(action = action.name(), "Performing niri effect");
+            if let Err(e) = self.actions.send(action.to_action(window.id)).await {
+                warn!(action = action.name(), error = %e, "niri effect failed");
+            }
+        }
+    }
+}
+
+impl EventHandler for NiriEffects {
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    fn events(&self) -> &[EventKind] {
+        &[EventKind::Focus, EventKind::Blur]
+    }
+
+    fn handle<'a>(&'a mut self, event: &'a NiriEvent) -> BoxFuture<'a, HandlerResult> {
+        Box::pin(async move {
+            self.apply_updates();
+            match event {
+                // niri reports every focus timestamp change; act on real focus moves only
+                NiriEvent::Focus { window, .. } if self.focused != Some(window.id) => {
+                    self.focused = Some(window.id);
+                    self.perform(window, |effect| effect.focus.as_ref()).await;
+                }
+                NiriEvent::Blur { window, .. } => {
+                    if self.focused == Some(window.id) {
+                        self.focused = None;
+                    }
+                    self.perform(window, |effect| effect.blur.as_ref()).await;
+                }
+                _ => {}
+            }
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use figment2::Figment;
+    use figment2::providers::{Format, Toml};
+
+    #[test]
+    fn test_effects_parse_into_niri_actions() {
+        let config: Config = Figment::new()
+            .merge(Toml::string(
+                r#"
+                [[effect.niri]]
+                focus = { action = "set-column-width", width = "66%" }
+                blur = { action = "set-column-width", width = "800" }
+
+                [[effect.niri]]
+                rule = "editor"
+                focus = { action = "center-column" }
+                "#,
+            ))
+            .extract()
+            .unwrap();
+        config.validate().unwrap();
+
+        let [resize, center] = config.effect.niri.as_slice() else {
+            panic!("expected two effects");
+        };
+        assert!(matches!(
+            resize.focus.as_ref().unwrap().to_action(7),
+            Action::SetWindowWidth {
+                id: Some(7),
+                change: SizeChange::SetProportion(66.0)
+            }
+        ));
+        assert!(matches!(
+            resize.blur.as_ref().unwrap().to_action(7),
+            Action::SetWindowWidth {
+                id: Some(7),
+                change: SizeChange::SetFixed(800)
+            }
+        ));
+        assert!(resize.applies_to("default"));
+        assert!(center.applies_to("editor"));
+        assert!(!center.applies_to("default"));
+    }
+
+    #[test]
+    fn test_invalid_effects_are_rejected() {
+        let parse = |toml: &str| -> Result<Config, String> {
+            Figment::new()
+                .merge(Toml::string(toml))
+                .extract()
+                .map_err(|e| e.to_string())
+        };
+
+        assert!(
+            parse("[[effect.niri]]\nfocus = { action = \"set-column-width\", width = \"wide\" }\n")
+                .is_err()
+        );
+        assert!(parse("[[effect.niri]]\nfocus = { action = \"teleport\" }\n").is_err());
+
+        let config = parse("[[effect.niri]]\nblur = { action = \"maximize-column\" }\n").unwrap();
+        let error = config.validate().unwrap_err();
+        assert!(error.contains("maximize-column"), "{}", error);
+    }
+}
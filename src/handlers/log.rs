@@ -0,0 +1,64 @@
+use futures::future::BoxFuture;
+use serde::Deserialize;
+use tracing::info;
+
+use super::{EventHandler, HandlerConfig, HandlerResult};
+use crate::niri::types::NiriEvent;
+
+/// `[handler.log]` settings
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct LogSettings {
+    /// Only log windows of this app; events about no window are always logged
+    app_id: Option<String>,
+}
+
+/// Logs the niri events it receives, for watching what the tracker sees
+#[derive(Debug, Default)]
+pub struct LogHandler {
+    app_id: Option<String>,
+}
+
+impl LogHandler {
+    pub const NAME: &'static str = "log";
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Log only windows of `app_id`
+    pub fn for_app(app_id: impl Into<String>) -> Self {
+        Self {
+            app_id: Some(app_id.into()),
+        }
+    }
+
+    pub fn from_config(config: &HandlerConfig) -> Result<Self, String> {
+        let settings: LogSettings = config.settings()?;
+        Ok(Self {
+            app_id: settings.app_id,
+        })
+    }
+}
+
+impl EventHandler for LogHandler {
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    fn handle<'a>(&'a mut self, event: &'a NiriEvent) -> BoxFuture<'a, HandlerResult> {
+        let window = event.window();
+        let wanted = self
+            .app_id
+            .as_deref()
+            .is_none_or(|app_id| window.is_none_or(|w| w.app_id.as_deref() == Some(app_id)));
+        if wanted {
+            info!(
+                app_id = window.and_then(|w| w.app_id.as_deref()),
+                title = window.and_then(|w| w.title.as_deref()),
+                "niri event"
+            );
+        }
+        Box::pin(async { Ok(()) })
+    }
+}
@@ -0,0 +1,552 @@
+pub mod log;
+
+use futures::future::BoxFuture;
+use futures::{FutureExt, Stream, StreamExt};
+use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::panic::AssertUnwindSafe;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tracing::{Instrument, error, info_span, warn};
+
+use crate::metrics::metrics;
+use crate::niri::types::NiriEvent;
+
+pub use self::log::LogHandler;
+
+/// Outcome of one handler on one event; errors are logged with the handler's name
+pub type HandlerResult = Result<(), String>;
+
+/// A kind of niri event, named as `NiriEvent::kind` names it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    Focus,
+    Blur,
+    Create,
+    Destroy,
+    Moved,
+    Resized,
+    OutputsChanged,
+}
+
+impl EventKind {
+    pub const ALL: &'static [EventKind] = &[
+        EventKind::Focus,
+        EventKind::Blur,
+        EventKind::Create,
+        EventKind::Destroy,
+        EventKind::Moved,
+        EventKind::Resized,
+        EventKind::OutputsChanged,
+    ];
+
+    pub fn of(event: &NiriEvent) -> Self {
+        match event {
+            NiriEvent::Focus { .. } => EventKind::Focus,
+            NiriEvent::Blur { .. } => EventKind::Blur,
+            NiriEvent::Create { .. } => EventKind::Create,
+            NiriEvent::Destroy { .. } => EventKind::Destroy,
+            NiriEvent::Moved { .. } => EventKind::Moved,
+            NiriEvent::Resized { .. } => EventKind::Resized,
+            NiriEvent::OutputsChanged { .. } => EventKind::OutputsChanged,
+        }
+    }
+}
+
+/// Something that consumes niri events beside the kitty pipeline
+///
+/// Every handler runs on a task of its own and receives the events of the kinds
+/// it consumes, in order. A handler returning an error is logged and keeps
+/// receiving events; one that panics is stopped. The other handlers carry on
+/// either way.
+pub trait EventHandler: Send {
+    /// Name used in logs and metrics when the handler is not built by name
+    fn name(&self) -> &'static str;
+
+    /// Event kinds handed to `handle`, unless `[handler.<name>] events` overrides them
+    fn events(&self) -> &[EventKind] {
+        EventKind::ALL
+    }
+
+    fn handle<'a>(&'a mut self, event: &'a NiriEvent) -> BoxFuture<'a, HandlerResult>;
+}
+
+/// One `[handler.<name>]` table: whether the handler runs, and its settings
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct HandlerConfig {
+    /// Set to false to turn off a handler that runs by default
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+
+    /// Event kinds to hand the handler, instead of the ones it asks for
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub events: Option<Vec<EventKind>>,
+
+    /// The handler's own settings: every other key of the table
+    #[serde(flatten)]
+    pub settings: serde_json::Map<String, serde_json::Value>,
+}
+
+/// `[handler]` tables by handler name
+pub type HandlersConfig = BTreeMap<String, HandlerConfig>;
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl Default for HandlerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            events: None,
+            settings: serde_json::Map::new(),
+        }
+    }
+}
+
+impl HandlerConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.events.as_ref().is_some_and(Vec::is_empty) {
+            return Err("events is empty, the handler would never run".to_string());
+        }
+        Ok(())
+    }
+
+    /// Parse the handler's own settings
+    pub fn settings<T: DeserializeOwned>(&self) -> Result<T, String> {
+        serde_json::from_value(serde_json::Value::Object(self.settings.clone()))
+            .map_err(|e| e.to_string())
+    }
+}
+
+type Factory = Box<dyn Fn(&HandlerConfig) -> Result<Box<dyn EventHandler>, String> + Send + Sync>;
+
+struct Registration {
+    factory: Factory,
+    /// Runs without a `[handler.<name>]` table
+    by_default: bool,
+}
+
+/// Event handlers known by name, built from `[handler.<name>]` tables
+#[derive(Default)]
+pub struct HandlerRegistry {
+    registered: BTreeMap<String, Registration>,
+}
+
+impl HandlerRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The handlers that ship with the crate, all off until configured
+    ///
+    /// Metrics and kitty focus effects are not among them: `NiriRegistry` counts
+    /// events as it emits them, and `KittyResizer` runs the effects in order with
+    /// the profile it resolves. `niri-effects` needs the daemon's config, so the
+    /// zoomer registers it.
+    pub fn builtin() -> Self {
+        Self::new().register(LogHandler::NAME, |config| {
+            Ok(Box::new(LogHandler::from_config(config)?))
+        })
+    }
+
+    /// Add a handler that runs when config.toml has a `[handler.<name>]` table
+    pub fn register<F>(self, name: &str, factory: F) -> Self
+    where
+        F: Fn(&HandlerConfig) -> Result<Box<dyn EventHandler>, String> + Send + Sync + 'static,
+    {
+        self.add(name, Box::new(factory), false)
+    }
+
+    /// Add a handler that runs unless its table sets `enabled = false`
+    pub fn register_default<F>(self, name: &str, factory: F) -> Self
+    where
+        F: Fn(&HandlerConfig) -> Result<Box<dyn EventHandler>, String> + Send + Sync + 'static,
+    {
+        self.add(name, Box::new(factory), true)
+    }
+
+    fn add(mut self, name: &str, factory: Factory, by_default: bool) -> Self {
+        self.registered.insert(
+            name.to_string(),
+            Registration {
+                factory,
+                by_default,
+            },
+        );
+        self
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.registered.keys().map(String::as_str)
+    }
+
+    /// Build the enabled handlers; a table naming no registered handler is an error
+    pub fn build(&self, config: &HandlersConfig) -> Result<Handlers, String> {
+        if let Some(unknown) = config
+            .keys()
+            .find(|name| !self.registered.contains_key(*name))
+        {
+            return Err(format!(
+                "Unknown handler '{}', expected one of: {}",
+                unknown,
+                self.names().collect::<Vec<_>>().join(", ")
+            ));
+        }
+
+        let mut handlers = Handlers::new();
+        let unconfigured = HandlerConfig::default();
+        for (name, registration) in &self.registered {
+            let handler_config = match config.get(name) {
+                Some(handler_config) => handler_config,
+                None if registration.by_default => &unconfigured,
+                None => continue,
+            };
+            if !handler_config.enabled {
+                continue;
+            }
+
+            let handler = (registration.factory)(handler_config)
+                .map_err(|e| format!("Invalid handler '{}': {}", name, e))?;
+            let events = handler_config
+                .events
+                .clone()
+                .unwrap_or_else(|| handler.events().to_vec());
+            handlers.push(name.clone(), events, handler);
+        }
+        Ok(handlers)
+    }
+}
+
+struct Entry {
+    name: String,
+    events: Vec<EventKind>,
+    handler: Box<dyn EventHandler>,
+}
+
+/// Event handlers to run, each with the event kinds it receives
+#[derive(Default)]
+pub struct Handlers {
+    entries: Vec<Entry>,
+}
+
+impl Handlers {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a handler for the event kinds it asks for
+    pub fn with(mut self, handler: impl EventHandler + 'static) -> Self {
+        let events = handler.events().to_vec();
+        self.push(handler.name().to_string(), events, Box::new(handler));
+        self
+    }
+
+    fn push(&mut self, name: String, events: Vec<EventKind>, handler: Box<dyn EventHandler>) {
+        self.entries.push(Entry {
+            name,
+            events,
+            handler,
+        });
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.entries
+            .iter()
+            .map(|entry| entry.name.as_str())
+            .collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Start every handler on a task of its own, fed through the returned dispatcher
+    ///
+    /// Each task ends once the dispatcher is dropped and its queued events are handled.
+    pub fn spawn(self) -> (Dispatcher, Vec<JoinHandle<()>>) {
+        let (subscribers, tasks) = self
+            .entries
+            .into_iter()
+            .map(|entry| {
+                let (tx, rx) = mpsc::unbounded_channel();
+                let task = tokio::spawn(run(entry.name, entry.handler, rx));
+                let subscriber = Subscriber {
+                    events: entry.events,
+                    tx,
+                };
+                (subscriber, task)
+            })
+            .unzip();
+        (Dispatcher { subscribers }, tasks)
+    }
+}
+
+struct Subscriber {
+    events: Vec<EventKind>,
+    tx: mpsc::UnboundedSender<NiriEvent>,
+}
+
+/// Hands niri events to running handlers; dropping it lets them finish
+pub struct Dispatcher {
+    subscribers: Vec<Subscriber>,
+}
+
+impl Dispatcher {
+    /// Queue `event` for every handler consuming its kind, without waiting on any
+    pub fn dispatch(&self, event: &NiriEvent) {
+        let kind = EventKind::of(event);
+        for subscriber in &self.subscribers {
+            // A stopped handler has dropped its receiver
+            if subscriber.events.contains(&kind) {
+                let _ = subscriber.tx.send(event.clone());
+            }
+        }
+    }
+
+    /// Pass `events` through, dispatching each on the way
+    pub fn tee<S>(self, events: S) -> impl Stream<Item = NiriEvent> + Send + Unpin
+    where
+        S: Stream<Item = NiriEvent> + Send + Unpin,
+    {
+        events.inspect(move |event| self.dispatch(event))
+    }
+}
+
+async fn run(
+    name: String,
+    mut handler: Box<dyn EventHandler>,
+    mut events: mpsc::UnboundedReceiver<NiriEvent>,
+) {
+    while let Some(event) = events.recv().await {
+        let span = info_span!(
+            "handler",
+            handler = %name,
+            kind = event.kind(),
+            window_id = event.window_id()
+        );
+        let outcome = AssertUnwindSafe(handler.handle(&event))
+            .catch_unwind()
+            .instrument(span.clone())
+            .await;
+        let _entered = span.enter();
+        match outcome {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => {
+                metrics().handler_failures.inc(&[&name, "error"]);
+                warn!(error = %e, "Event handler failed");
+            }
+            Err(_) => {
+                metrics().handler_failures.inc(&[&name, "panic"]);
+                error!("Event handler panicked, stopping it");
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::niri::types::WindowInfo;
+    use figment2::Figment;
+    use figment2::providers::{Format, Toml};
+
+    /// Reports the kind of every event it gets
+    struct Recorder {
+        events: Vec<EventKind>,
+        seen: mpsc::UnboundedSender<EventKind>,
+    }
+
+    impl Recorder {
+        fn new(events: &[EventKind]) -> (Self, mpsc::UnboundedReceiver<EventKind>) {
+            let (seen, rx) = mpsc::unbounded_channel();
+            let recorder = Self {
+                events: events.to_vec(),
+                seen,
+            };
+            (recorder, rx)
+        }
+    }
+
+    impl EventHandler for Recorder {
+        fn name(&self) -> &'static str {
+            "recorder"
+        }
+
+        fn events(&self) -> &[EventKind] {
+            &self.events
+        }
+
+        fn handle<'a>(&'a mut self, event: &'a NiriEvent) -> BoxFuture<'a, HandlerResult> {
+            let _ = self.seen.send(EventKind::of(event));
+            Box::pin(async { Ok(()) })
+        }
+    }
+
+    struct Failing;
+
+    impl EventHandler for Failing {
+        fn name(&self) -> &'static str {
+            "test-failing"
+        }
+
+        fn handle<'a>(&'a mut self, _event: &'a NiriEvent) -> BoxFuture<'a, HandlerResult> {
+            Box::pin(async { Err("no luck".to_string()) })
+        }
+    }
+
+    struct Panicking;
+
+    impl EventHandler for Panicking {
+        fn name(&self) -> &'static str {
+            "test-panicking"
+        }
+
+        fn handle<'a>(&'a mut self, _event: &'a NiriEvent) -> BoxFuture<'a, HandlerResult> {
+            Box::pin(async { panic!("handler bug") })
+        }
+    }
+
+    fn focus(id: u64) -> NiriEvent {
+        NiriEvent::Focus {
+            window_id: id,
+            window: WindowInfo {
+                id,
+                ..WindowInfo::default()
+            },
+        }
+    }
+
+    fn blur(id: u64) -> NiriEvent {
+        NiriEvent::Blur {
+            window_id: id,
+            window: WindowInfo {
+                id,
+                ..WindowInfo::default()
+            },
+        }
+    }
+
+    /// Everything a handler saw once the dispatcher is gone
+    async fn drain(mut seen: mpsc::UnboundedReceiver<EventKind>) -> Vec<EventKind> {
+        let mut kinds = Vec::new();
+        while let Some(kind) = seen.recv().await {
+            kinds.push(kind);
+        }
+        kinds
+    }
+
+    fn parse(toml: &str) -> HandlersConfig {
+        #[derive(Deserialize)]
+        struct Tables {
+            handler: HandlersConfig,
+        }
+        Figment::new()
+            .merge(Toml::string(toml))
+            .extract::<Tables>()
+            .unwrap()
+            .handler
+    }
+
+    #[tokio::test]
+    async fn test_handlers_only_get_the_kinds_they_consume() {
+        let (focus_only, focus_seen) = Recorder::new(&[EventKind::Focus]);
+        let (everything, all_seen) = Recorder::new(EventKind::ALL);
+        let (dispatcher, _) = Handlers::new().with(focus_only).with(everything).spawn();
+
+        for event in [
+            focus(1),
+            blur(1),
+            NiriEvent::Destroy { window_id: 1 },
+            focus(2),
+        ] {
+            dispatcher.dispatch(&event);
+        }
+        drop(dispatcher);
+
+        assert_eq!(
+            drain(focus_seen).await,
+            vec![EventKind::Focus, EventKind::Focus]
+        );
+        assert_eq!(
+            drain(all_seen).await,
+            vec![
+                EventKind::Focus,
+                EventKind::Blur,
+                EventKind::Destroy,
+                EventKind::Focus
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_failing_and_panicking_handlers_do_not_stop_the_others() {
+        let (recorder, seen) = Recorder::new(EventKind::ALL);
+        let (dispatcher, tasks) = Handlers::new()
+            .with(Panicking)
+            .with(Failing)
+            .with(recorder)
+            .spawn();
+
+        for event in [focus(1), blur(1), focus(2)] {
+            dispatcher.dispatch(&event);
+        }
+        drop(dispatcher);
+        for task in tasks {
+            task.await.unwrap();
+        }
+
+        assert_eq!(drain(seen).await.len(), 3);
+        let failures = &metrics().handler_failures;
+        assert_eq!(failures.get(&["test-failing", "error"]), 3);
+        assert_eq!(failures.get(&["test-panicking", "panic"]), 1);
+    }
+
+    #[test]
+    fn test_handlers_are_built_by_name_from_config() {
+        let registry = HandlerRegistry::new()
+            .register("opt-in", |_| Ok(Box::new(Failing)))
+            .register_default("always", |_| Ok(Box::new(Failing)));
+
+        let handlers = registry.build(&HandlersConfig::new()).unwrap();
+        assert_eq!(handlers.names(), vec!["always"]);
+
+        let handlers = registry
+            .build(&parse(
+                r#"
+                [handler.opt-in]
+                events = ["blur", "outputs_changed"]
+
+                [handler.always]
+                enabled = false
+                "#,
+            ))
+            .unwrap();
+        assert_eq!(handlers.names(), vec!["opt-in"]);
+        assert_eq!(
+            handlers.entries[0].events,
+            vec![EventKind::Blur, EventKind::OutputsChanged]
+        );
+
+        let error = registry.build(&parse("[handler.opt-inn]\n")).err().unwrap();
+        assert!(error.contains("opt-inn"), "{}", error);
+    }
+
+    #[test]
+    fn test_handler_settings_reach_the_factory() {
+        let config = parse("[handler.log]\napp_id = \"kitty\"\n");
+        let handlers = HandlerRegistry::builtin().build(&config).unwrap();
+        assert_eq!(handlers.names(), vec!["log"]);
+
+        let config = parse("[handler.log]\napp = \"kitty\"\n");
+        let error = HandlerRegistry::builtin().build(&config).err().unwrap();
+        assert!(error.contains("app"), "{}", error);
+
+        let config = parse("[handler.log]\nevents = []\n");
+        assert!(config["log"].validate().is_err());
+    }
+}
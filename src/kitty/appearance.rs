@@ -0,0 +1,208 @@
+use crate::kitty::colors::Colors;
+use crate::kitty::{KittyRegistry, ZoomingResult};
+use futures::future::BoxFuture;
+use std::error::Error;
+
+/// The opacity, color and spacing operations the kitty effects need
+///
+/// `KittyRegistry` sends them over kitty's remote control socket; tests use
+/// `FakeAppearance`, which keeps each window's looks in memory.
+pub trait AppearanceController: Send + Sync {
+    /// Set the background opacity, which needs `dynamic_background_opacity yes`
+    fn set_background_opacity(
+        &self,
+        pid: i32,
+        opacity: f64,
+    ) -> BoxFuture<'_, Result<ZoomingResult, Box<dyn Error>>>;
+
+    /// Set the colors named in `colors`
+    fn set_colors<'a>(
+        &'a self,
+        pid: i32,
+        colors: &'a Colors,
+    ) -> BoxFuture<'a, Result<ZoomingResult, Box<dyn Error>>>;
+
+    /// Go back to kitty.conf's colors
+    fn reset_colors(&self, pid: i32) -> BoxFuture<'_, ZoomingResult>;
+
+    /// kitty.conf's colors, as `pid`'s kitty reports them
+    fn configured_colors(&self, pid: i32) -> BoxFuture<'_, Option<Colors>>;
+
+    /// Set window padding and margin; `None` goes back to kitty.conf's
+    fn set_spacing(
+        &self,
+        pid: i32,
+        padding: Option<f64>,
+        margin: Option<f64>,
+    ) -> BoxFuture<'_, Result<ZoomingResult, Box<dyn Error>>>;
+}
+
+impl AppearanceController for KittyRegistry {
+    fn set_background_opacity(
+        &self,
+        pid: i32,
+        opacity: f64,
+    ) -> BoxFuture<'_, Result<ZoomingResult, Box<dyn Error>>> {
+        Box::pin(KittyRegistry::set_background_opacity(self, pid, opacity))
+    }
+
+    fn set_colors<'a>(
+        &'a self,
+        pid: i32,
+        colors: &'a Colors,
+    ) -> BoxFuture<'a, Result<ZoomingResult, Box<dyn Error>>> {
+        Box::pin(KittyRegistry::set_colors(self, pid, colors))
+    }
+
+    fn reset_colors(&self, pid: i32) -> BoxFuture<'_, ZoomingResult> {
+        Box::pin(KittyRegistry::reset_colors(self, pid))
+    }
+
+    fn configured_colors(&self, pid: i32) -> BoxFuture<'_, Option<Colors>> {
+        Box::pin(KittyRegistry::configured_colors(self, pid))
+    }
+
+    fn set_spacing(
+        &self,
+        pid: i32,
+        padding: Option<f64>,
+        margin: Option<f64>,
+    ) -> BoxFuture<'_, Result<ZoomingResult, Box<dyn Error>>> {
+        Box::pin(KittyRegistry::set_spacing(self, pid, padding, margin))
+    }
+}
+
+#[cfg(test)]
+pub use fake::FakeAppearance;
+
+#[cfg(test)]
+mod fake {
+    use super::*;
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    /// What one window in the fake kitty looks like; `None` is kitty.conf's
+    #[derive(Debug, Clone, Default, PartialEq)]
+    struct Looks {
+        opacity: Option<f64>,
+        colors: Option<Colors>,
+        spacing: (Option<f64>, Option<f64>),
+    }
+
+    /// kitty in memory: opacity, colors and spacing per PID
+    #[derive(Debug, Default)]
+    pub struct FakeAppearance {
+        /// What `configured_colors` reports, as kitty.conf's colors
+        configured: Option<Colors>,
+        looks: Mutex<HashMap<i32, Looks>>,
+        /// Every command sent, as `(pid, command)`, in order
+        sent: Mutex<Vec<(i32, &'static str)>>,
+    }
+
+    impl FakeAppearance {
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        /// Report `colors` as kitty.conf's colors
+        pub fn with_configured_colors(mut self, colors: Colors) -> Self {
+            self.configured = Some(colors);
+            self
+        }
+
+        /// The background opacity `pid` was given, if any
+        pub fn opacity(&self, pid: i32) -> Option<f64> {
+            self.looks(pid).opacity
+        }
+
+        /// The colors `pid` was given, if not reset to kitty.conf's
+        pub fn colors(&self, pid: i32) -> Option<Colors> {
+            self.looks(pid).colors
+        }
+
+        /// The `(padding, margin)` `pid` was given
+        pub fn spacing(&self, pid: i32) -> (Option<f64>, Option<f64>) {
+            self.looks(pid).spacing
+        }
+
+        /// Every command sent to `pid`, in order
+        pub fn sent(&self, pid: i32) -> Vec<&'static str> {
+            self.sent
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|(sent_to, _)| *sent_to == pid)
+                .map(|(_, command)| *command)
+                .collect()
+        }
+
+        fn looks(&self, pid: i32) -> Looks {
+            self.looks
+                .lock()
+                .unwrap()
+                .get(&pid)
+                .cloned()
+                .unwrap_or_default()
+        }
+
+        fn update(&self, pid: i32, command: &'static str, change: impl FnOnce(&mut Looks)) {
+            change(self.looks.lock().unwrap().entry(pid).or_default());
+            self.sent.lock().unwrap().push((pid, command));
+        }
+    }
+
+    fn success(pid: i32) -> ZoomingResult {
+        ZoomingResult::Success {
+            pid,
+            font_adjustment: String::new(),
+            font_size: None,
+            clamped: false,
+        }
+    }
+
+    impl AppearanceController for FakeAppearance {
+        fn set_background_opacity(
+            &self,
+            pid: i32,
+            opacity: f64,
+        ) -> BoxFuture<'_, Result<ZoomingResult, Box<dyn Error>>> {
+            self.update(pid, "set-background-opacity", |looks| {
+                looks.opacity = Some(opacity)
+            });
+            Box::pin(async move { Ok(success(pid)) })
+        }
+
+        fn set_colors<'a>(
+            &'a self,
+            pid: i32,
+            colors: &'a Colors,
+        ) -> BoxFuture<'a, Result<ZoomingResult, Box<dyn Error>>> {
+            self.update(pid, "set-colors", |looks| {
+                looks.colors = Some(colors.clone())
+            });
+            Box::pin(async move { Ok(success(pid)) })
+        }
+
+        fn reset_colors(&self, pid: i32) -> BoxFuture<'_, ZoomingResult> {
+            self.update(pid, "set-colors --reset", |looks| looks.colors = None);
+            Box::pin(async move { success(pid) })
+        }
+
+        fn configured_colors(&self, _pid: i32) -> BoxFuture<'_, Option<Colors>> {
+            let colors = self.configured.clone();
+            Box::pin(async move { colors })
+        }
+
+        fn set_spacing(
+            &self,
+            pid: i32,
+            padding: Option<f64>,
+            margin: Option<f64>,
+        ) -> BoxFuture<'_, Result<ZoomingResult, Box<dyn Error>>> {
+            self.update(pid, "set-spacing", |looks| {
+                looks.spacing = (padding, margin)
+            });
+            Box::pin(async move { Ok(success(pid)) })
+        }
+    }
+}
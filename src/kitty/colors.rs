@@ -0,0 +1,105 @@
+use crate::kitty::conf_parser::read_config_with_includes;
+use serde_json::{Map, Value};
+use std::path::{Path, PathBuf};
+
+/// Color name to 0xRRGGBB value, as kitty's `set-colors` payload takes them
+pub type Colors = Map<String, Value>;
+
+/// Every `name #rrggbb` (or `#rgb`) setting in kitty.conf-style content
+///
+/// This covers theme files, kitty.conf and `get-colors` output alike; settings
+/// whose value is not a hex color are skipped. Later settings win.
+pub fn parse_colors(content: &str) -> Colors {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| {
+            let (name, value) = line.split_once(char::is_whitespace)?;
+            let rgb = parse_color(value.trim())?;
+            Some((name.to_string(), Value::from(rgb)))
+        })
+        .collect()
+}
+
+/// Load a kitty theme `.conf`, following its includes; `~/` expands to the home directory
+pub fn load_theme(path: &Path) -> Result<Colors, String> {
+    let path = match path.strip_prefix("~") {
+        Ok(rest) => dirs::home_dir()
+            .ok_or_else(|| "Home directory not found".to_string())?
+            .join(rest),
+        Err(_) => PathBuf::from(path),
+    };
+
+    let colors = parse_colors(&read_config_with_includes(&path)?);
+    if colors.is_empty() {
+        return Err(format!("{} sets no colors", path.display()));
+    }
+    Ok(colors)
+}
+
+/// Move every color `amount` of the way (0.0 to 1.0) towards gray of the same luminance
+pub fn desaturate(colors: &Colors, amount: f64) -> Colors {
+    colors
+        .iter()
+        .map(|(name, value)| {
+            let value = match value.as_u64() {
+                Some(rgb) => Value::from(desaturate_rgb(rgb as u32, amount)),
+                None => value.clone(),
+            };
+            (name.clone(), value)
+        })
+        .collect()
+}
+
+fn parse_color(value: &str) -> Option<u32> {
+    let hex = value.strip_prefix('#')?;
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    match hex.len() {
+        6 => u32::from_str_radix(hex, 16).ok(),
+        3 => {
+            let short = u32::from_str_radix(hex, 16).ok()?;
+            let (r, g, b) = ((short >> 8) & 0xf, (short >> 4) & 0xf, short & 0xf);
+            Some(((r * 0x11) << 16) | ((g * 0x11) << 8) | (b * 0x11))
+        }
+        _ => None,
+    }
+}
+
+fn desaturate_rgb(rgb: u32, amount: f64) -> u32 {
+    let channels = [(rgb >> 16) & 0xff, (rgb >> 8) & 0xff, rgb & 0xff].map(f64::from);
+    let luma = 0.2126 * channels[0] + 0.7152 * channels[1] + 0.0722 * channels[2];
+    let [r, g, b] = channels.map(|c| (c + (luma - c) * amount).round().clamp(0.0, 255.0) as u32);
+    (r << 16) | (g << 8) | b
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_colors_from_theme_content() {
+        let colors = parse_colors(
+            "# Muted\nforeground #a0a0a0\nbackground #123\ncolor1 #zzzzzz\ncursor none\n\
+             background_opacity 0.9\ncolor1 #ff0000\n",
+        );
+
+        assert_eq!(colors.len(), 3);
+        assert_eq!(colors["foreground"], 0xa0a0a0);
+        assert_eq!(colors["background"], 0x112233);
+        assert_eq!(colors["color1"], 0xff0000);
+    }
+
+    #[test]
+    fn test_desaturate_moves_towards_gray() {
+        let colors = parse_colors("color1 #ff0000\ncolor7 #808080\n");
+
+        let gray = desaturate(&colors, 1.0);
+        assert_eq!(gray["color1"], 0x363636);
+        assert_eq!(gray["color7"], 0x808080);
+
+        assert_eq!(desaturate(&colors, 0.0), colors);
+    }
+}
@@ -1,17 +1,81 @@
 use std::fs;
-use std::path::PathBuf;
-use std::sync::OnceLock;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
 
 const KITTY_CONF_NAME: &str = "kitty.conf";
 
-static BASELINE_FONT_SIZE: OnceLock<Option<f64>> = OnceLock::new();
+const MAX_INCLUDE_DEPTH: usize = 8;
+
+/// kitty's own default when kitty.conf does not set `background_opacity`
+const DEFAULT_BACKGROUND_OPACITY: f64 = 1.0;
+
+/// `None` until kitty.conf is first read; then the resolved `font_size`, if any
+static BASELINE_FONT_SIZE: RwLock<Option<Option<f64>>> = RwLock::new(None);
+
+/// `None` until kitty.conf is first read; then its `background_opacity`
+static BACKGROUND_OPACITY: RwLock<Option<f64>> = RwLock::new(None);
 
 pub fn get_baseline_font_size() -> Option<f64> {
-    *BASELINE_FONT_SIZE.get_or_init(|| parse_font_size(None).ok())
+    if let Some(size) = *BASELINE_FONT_SIZE.read().unwrap() {
+        return size;
+    }
+    refresh_baseline_font_size(None)
 }
 
 pub fn set_baseline_font_size(size: f64) {
-    let _ = BASELINE_FONT_SIZE.set(Some(size));
+    *BASELINE_FONT_SIZE.write().unwrap() = Some(Some(size));
+}
+
+/// Re-read `font_size` from kitty.conf (the default one when `config_path` is `None`)
+/// and cache it as the new baseline
+pub fn refresh_baseline_font_size(config_path: Option<PathBuf>) -> Option<f64> {
+    let size = parse_font_size(config_path).ok();
+    *BASELINE_FONT_SIZE.write().unwrap() = Some(size);
+    size
+}
+
+/// The `background_opacity` kitty.conf configures, read once and then cached
+pub fn get_background_opacity() -> f64 {
+    if let Some(opacity) = *BACKGROUND_OPACITY.read().unwrap() {
+        return opacity;
+    }
+    refresh_background_opacity(None)
+}
+
+/// Re-read `background_opacity` from kitty.conf (the default one when `config_path`
+/// is `None`) and cache it
+pub fn refresh_background_opacity(config_path: Option<PathBuf>) -> f64 {
+    let opacity = read_effective_config(config_path)
+        .map_or(DEFAULT_BACKGROUND_OPACITY, |content| {
+            background_opacity(&content)
+        });
+    *BACKGROUND_OPACITY.write().unwrap() = Some(opacity);
+    opacity
+}
+
+/// Whether kitty.conf lets remote control change the background opacity
+pub fn dynamic_background_opacity(config_path: Option<PathBuf>) -> bool {
+    read_effective_config(config_path).is_some_and(|content| {
+        matches!(
+            read_settings(&content, "dynamic_background_opacity")
+                .pop()
+                .as_deref(),
+            Some("yes" | "y" | "true")
+        )
+    })
+}
+
+fn background_opacity(content: &str) -> f64 {
+    read_settings(content, "background_opacity")
+        .pop()
+        .and_then(|value| value.parse::<f64>().ok())
+        .filter(|opacity| (0.0..=1.0).contains(opacity))
+        .unwrap_or(DEFAULT_BACKGROUND_OPACITY)
+}
+
+fn read_effective_config(config_path: Option<PathBuf>) -> Option<String> {
+    let path = config_path.or_else(|| get_kitty_config_path().ok())?;
+    read_config_with_includes(&path).ok()
 }
 
 pub fn get_kitty_config_path() -> Result<PathBuf, String> {
@@ -36,29 +100,96 @@ pub fn parse_font_size(config_path: Option<PathBuf>) -> Result<f64, String> {
         .or_else(|| get_kitty_config_path().ok())
         .ok_or_else(|| "No config path provided and could not find default".to_string())?;
 
-    let content =
-        fs::read_to_string(&conf_path).map_err(|e| format!("Failed to read config file: {}", e))?;
+    let content = read_config_with_includes(&conf_path)?;
+
+    // kitty applies the last font_size, which may come from an included file
+    let Some(value) = read_settings(&content, "font_size").pop() else {
+        return Err("font_size not found in kitty.conf".to_string());
+    };
+    if value.is_empty() {
+        return Err("font_size found but has no value".to_string());
+    }
+
+    value
+        .parse::<f64>()
+        .map_err(|e| format!("Failed to parse font_size value '{}': {}", value, e))
+}
+
+/// Read kitty.conf with `include` lines replaced by the included files' contents
+///
+/// Relative paths resolve against the including file's directory and `~` expands to
+/// the home directory. Missing includes are skipped, as kitty does.
+pub fn read_config_with_includes(path: &Path) -> Result<String, String> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    Ok(inline_includes(&content, path.parent(), 0, &mut Vec::new()))
+}
+
+/// kitty.conf plus every file it includes, whether or not they exist yet
+pub fn config_files(path: &Path) -> Vec<PathBuf> {
+    let mut files = vec![path.to_path_buf()];
+    if let Ok(content) = fs::read_to_string(path) {
+        inline_includes(&content, path.parent(), 0, &mut files);
+    }
+    files
+}
+
+fn inline_includes(
+    content: &str,
+    base_dir: Option<&Path>,
+    depth: usize,
+    files: &mut Vec<PathBuf>,
+) -> String {
+    let mut out = String::with_capacity(content.len());
 
     for line in content.lines() {
-        let line = line.trim();
+        let include = line
+            .trim()
+            .strip_prefix("include")
+            .filter(|rest| rest.starts_with(char::is_whitespace))
+            .map(str::trim);
 
-        if line.is_empty() || line.starts_with('#') {
+        let Some(include) = include.filter(|_| depth < MAX_INCLUDE_DEPTH) else {
+            out.push_str(line);
+            out.push('\n');
             continue;
-        }
+        };
 
-        if let Some(rest) = line.strip_prefix("font_size") {
-            let rest = rest.trim();
-            if rest.is_empty() {
-                return Err("font_size found but has no value".to_string());
-            }
+        let include_path = match include.strip_prefix("~/") {
+            Some(rest) => dirs::home_dir().map(|home| home.join(rest)),
+            None => Some(base_dir.map_or_else(|| PathBuf::from(include), |dir| dir.join(include))),
+        };
 
-            return rest
-                .parse::<f64>()
-                .map_err(|e| format!("Failed to parse font_size value '{}': {}", rest, e));
+        let Some(include_path) = include_path else {
+            continue;
+        };
+        if let Ok(included) = fs::read_to_string(&include_path) {
+            out.push_str(&inline_includes(
+                &included,
+                include_path.parent(),
+                depth + 1,
+                files,
+            ));
         }
+        files.push(include_path);
     }
 
-    Err("font_size not found in kitty.conf".to_string())
+    out
+}
+
+/// Every value given for `key` in kitty.conf content, in file order
+///
+/// kitty applies the last occurrence of most settings.
+pub fn read_settings(content: &str, key: &str) -> Vec<String> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let (name, value) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+            (name == key).then(|| value.trim().to_string())
+        })
+        .collect()
 }
 
 #[cfg(test)]
@@ -129,4 +260,81 @@ mod tests {
         assert!(result.is_err());
         assert!(result.unwrap_err().contains("has no value"));
     }
+
+    #[test]
+    fn test_parse_font_size_follows_includes() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("kitty.conf"),
+            "font_size 11\ninclude fonts.conf\n",
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("fonts.conf"), "font_size 13.5\n").unwrap();
+
+        let conf_path = dir.path().join("kitty.conf");
+        assert_eq!(parse_font_size(Some(conf_path.clone())), Ok(13.5));
+        assert_eq!(
+            config_files(&conf_path),
+            vec![conf_path.clone(), dir.path().join("fonts.conf")]
+        );
+    }
+
+    #[test]
+    fn test_read_settings_matches_whole_key() {
+        let content = "allow_remote_control no\n\
+                       # allow_remote_control yes\n\
+                       allow_remote_control_extra x\n\
+                       allow_remote_control   password\n";
+
+        assert_eq!(
+            read_settings(content, "allow_remote_control"),
+            vec!["no".to_string(), "password".to_string()]
+        );
+        assert!(read_settings(content, "listen_on").is_empty());
+    }
+
+    #[test]
+    fn test_read_config_with_includes() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("conf.d")).unwrap();
+        std::fs::write(
+            dir.path().join("kitty.conf"),
+            "font_size 11\ninclude conf.d/remote.conf\ninclude missing.conf\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("conf.d/remote.conf"),
+            "allow_remote_control password\ninclude nested.conf\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("conf.d/nested.conf"),
+            "listen_on unix:/tmp/k\n",
+        )
+        .unwrap();
+
+        let content = read_config_with_includes(&dir.path().join("kitty.conf")).unwrap();
+        assert_eq!(
+            read_settings(&content, "allow_remote_control"),
+            vec!["password"]
+        );
+        assert_eq!(read_settings(&content, "listen_on"), vec!["unix:/tmp/k"]);
+        assert!(read_settings(&content, "include").is_empty());
+    }
+
+    #[test]
+    fn test_background_opacity_settings() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        writeln!(temp_file, "background_opacity 0.9").unwrap();
+        writeln!(temp_file, "dynamic_background_opacity yes").unwrap();
+        writeln!(temp_file, "background_opacity 0.85").unwrap();
+
+        let path = temp_file.path().to_path_buf();
+        let content = read_config_with_includes(&path).unwrap();
+        assert_eq!(background_opacity(&content), 0.85);
+        assert!(dynamic_background_opacity(Some(path)));
+
+        assert_eq!(background_opacity("font_size 11\n"), 1.0);
+        assert_eq!(background_opacity("background_opacity 1.5\n"), 1.0);
+    }
 }
@@ -0,0 +1,129 @@
+use crate::kitty::{KittyRegistry, ZoomingResult};
+use futures::future::BoxFuture;
+use std::error::Error;
+
+/// The font operations zoom needs from kitty
+///
+/// `KittyRegistry` sends them over kitty's remote control socket; tests use
+/// `FakeFonts`, which keeps the sizes in memory.
+pub trait FontController: Send + Sync {
+    /// Set an absolute font size, clamped to the registry's sanity limits
+    fn set_font_size(
+        &self,
+        pid: i32,
+        size: f64,
+    ) -> BoxFuture<'_, Result<ZoomingResult, Box<dyn Error>>>;
+
+    /// Text columns of the focused window in `pid`'s kitty, if it reports them
+    fn columns(&self, pid: i32) -> BoxFuture<'_, Option<u32>>;
+}
+
+impl FontController for KittyRegistry {
+    fn set_font_size(
+        &self,
+        pid: i32,
+        size: f64,
+    ) -> BoxFuture<'_, Result<ZoomingResult, Box<dyn Error>>> {
+        Box::pin(KittyRegistry::set_font_size(self, pid, size))
+    }
+
+    fn columns(&self, pid: i32) -> BoxFuture<'_, Option<u32>> {
+        Box::pin(KittyRegistry::columns(self, pid))
+    }
+}
+
+#[cfg(test)]
+pub use fake::FakeFonts;
+
+#[cfg(test)]
+mod fake {
+    use super::*;
+    use crate::kitty::registry::{MAX_FONT_SIZE, MIN_FONT_SIZE};
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    /// kitty in memory: one font size per PID, clamped like `KittyRegistry`
+    #[derive(Debug, Default)]
+    pub struct FakeFonts {
+        /// Size of windows no size was sent to yet
+        baseline: f64,
+        sizes: Mutex<HashMap<i32, f64>>,
+        sent: Mutex<Vec<(i32, f64)>>,
+        /// Text columns at 1pt; a window shows `columns / size` columns
+        columns: Option<f64>,
+        /// Fail every size change, as a kitty that went away would
+        failing: bool,
+    }
+
+    impl FakeFonts {
+        pub fn new(baseline: f64) -> Self {
+            Self {
+                baseline,
+                ..Self::default()
+            }
+        }
+
+        /// Report `columns` at `size` points, scaling inversely with font size
+        pub fn with_columns(mut self, columns: u32, size: f64) -> Self {
+            self.columns = Some(f64::from(columns) * size);
+            self
+        }
+
+        /// Fail every `set_font_size` without changing anything
+        pub fn failing(mut self) -> Self {
+            self.failing = true;
+            self
+        }
+
+        /// The size `pid` shows now
+        pub fn size(&self, pid: i32) -> f64 {
+            self.sizes
+                .lock()
+                .unwrap()
+                .get(&pid)
+                .copied()
+                .unwrap_or(self.baseline)
+        }
+
+        /// Every size sent to `pid`, in order
+        pub fn sent(&self, pid: i32) -> Vec<f64> {
+            self.sent
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|(sent_to, _)| *sent_to == pid)
+                .map(|(_, size)| *size)
+                .collect()
+        }
+    }
+
+    impl FontController for FakeFonts {
+        fn set_font_size(
+            &self,
+            pid: i32,
+            size: f64,
+        ) -> BoxFuture<'_, Result<ZoomingResult, Box<dyn Error>>> {
+            if self.failing {
+                return Box::pin(async { Err("kitty went away".into()) });
+            }
+            let applied = size.clamp(MIN_FONT_SIZE, MAX_FONT_SIZE);
+            self.sizes.lock().unwrap().insert(pid, applied);
+            self.sent.lock().unwrap().push((pid, applied));
+            Box::pin(async move {
+                Ok(ZoomingResult::Success {
+                    pid,
+                    font_adjustment: format!("={}", applied),
+                    font_size: Some(applied),
+                    clamped: applied != size,
+                })
+            })
+        }
+
+        fn columns(&self, pid: i32) -> BoxFuture<'_, Option<u32>> {
+            let columns = self
+                .columns
+                .map(|columns| (columns / self.size(pid)).floor() as u32);
+            Box::pin(async move { columns })
+        }
+    }
+}
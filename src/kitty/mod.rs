@@ -1,9 +1,13 @@
+pub mod appearance;
+pub mod colors;
 pub mod conf_parser;
+pub mod font;
 pub mod process;
 pub mod registry;
 pub mod resizer;
 pub mod types;
 pub mod util;
+pub mod zoom;
 
 pub use registry::KittyRegistry;
 pub use resizer::KittyResizer;
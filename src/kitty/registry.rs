@@ -1,27 +1,46 @@
-use crate::config::{RegistryConfig, Verbosity};
-use crate::kitty::types::{KittyConnectionStatus, ZoomingResult};
+use crate::config::RegistryConfig;
+use crate::kitty::ZoomingResult;
+use crate::kitty::colors::{Colors, parse_colors};
+use crate::kitty::conf_parser::get_baseline_font_size;
+use crate::kitty::types::{KittyConnectionStatus, KittyInstanceStatus};
 use crate::kitty::util::{get_kitty_password, get_kitty_socket_path, is_process_alive};
+use crate::metrics::metrics;
 use dashmap::DashMap;
-use kitty_rc::Kitty;
-use kitty_rc::commands::SetFontSizeCommand;
-use std::collections::HashMap;
+use kitty_rc::command::CommandBuilder;
+use kitty_rc::commands::window::{OsInstance, parse_response_data};
+use kitty_rc::commands::{
+    GetColorsCommand, LsCommand, SetBackgroundOpacityCommand, SetColorsCommand, SetFontSizeCommand,
+    SetSpacingCommand,
+};
+use kitty_rc::{Kitty, KittyMessage};
+use std::collections::{BTreeMap, HashMap};
 use std::path::PathBuf;
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
 use std::time::{Duration, Instant};
 use tokio::sync::Mutex;
 use tokio::time::sleep;
+use tracing::{debug, info, instrument, trace, warn};
+
+/// Hard limits for absolute font sizes, whatever the zoom config says
+pub const MIN_FONT_SIZE: f64 = 4.0;
+pub const MAX_FONT_SIZE: f64 = 200.0;
+
+/// Font size assumed when kitty.conf does not set `font_size`
+pub const DEFAULT_FONT_SIZE: f64 = 12.0;
 
 struct ManagedConnection {
     client: Arc<Mutex<Kitty>>,
     last_used: Instant,
 }
 
+#[derive(Clone)]
 pub struct KittyRegistry {
     connections: Arc<Mutex<HashMap<i32, ManagedConnection>>>,
     statuses: Arc<Mutex<HashMap<i32, KittyConnectionStatus>>>,
     pid_cache: Arc<DashMap<i32, i32>>,
-    config: RegistryConfig,
-    verbosity: Verbosity,
+    /// Last font size kitty confirmed per PID, for the relative font commands
+    font_sizes: Arc<DashMap<i32, f64>>,
+    config: Arc<RwLock<RegistryConfig>>,
 }
 
 impl KittyRegistry {
@@ -30,38 +49,36 @@ impl KittyRegistry {
             connections: Arc::new(Mutex::new(HashMap::new())),
             statuses: Arc::new(Mutex::new(HashMap::new())),
             pid_cache: Arc::new(DashMap::new()),
-            config,
-            verbosity: Verbosity::Info,
+            font_sizes: Arc::new(DashMap::new()),
+            config: Arc::new(RwLock::new(config)),
         }
     }
 
-    pub fn with_defaults() -> Self {
-        Self::new(RegistryConfig::default())
+    /// Snapshot of the current pool settings
+    pub fn config(&self) -> RegistryConfig {
+        self.config.read().unwrap().clone()
     }
 
-    pub fn with_verbosity(config: RegistryConfig, verbosity: Verbosity) -> Self {
-        Self {
-            connections: Arc::new(Mutex::new(HashMap::new())),
-            statuses: Arc::new(Mutex::new(HashMap::new())),
-            pid_cache: Arc::new(DashMap::new()),
-            config,
-            verbosity,
-        }
+    /// Swap in new pool settings; open connections are kept and the reaper
+    /// picks up the new timings on its next pass
+    pub fn set_config(&self, config: RegistryConfig) {
+        *self.config.write().unwrap() = config;
     }
 
-    pub fn verbosity(&self) -> Verbosity {
-        self.verbosity
+    pub fn with_defaults() -> Self {
+        Self::new(RegistryConfig::default())
     }
 
     pub async fn start_reaper(&self) {
         let connections = Arc::clone(&self.connections);
         let statuses = Arc::clone(&self.statuses);
-        let idle_timeout = self.config.idle_timeout;
-        let reap_interval = self.config.reap_interval;
+        let config = Arc::clone(&self.config);
 
         tokio::spawn(async move {
             loop {
+                let reap_interval = config.read().unwrap().reap_interval;
                 sleep(reap_interval).await;
+                let idle_timeout = config.read().unwrap().idle_timeout;
 
                 let mut to_remove = Vec::new();
 
@@ -75,11 +92,12 @@ impl KittyRegistry {
 
                         if is_dead || is_idle {
                             if is_dead {
-                                eprintln!("Reaping dead PID {}", pid);
+                                info!(kitty_pid = pid, "Reaping dead kitty connection");
                             } else {
-                                eprintln!(
-                                    "Reaping idle PID {} (unused for >{:?})",
-                                    pid, idle_timeout
+                                info!(
+                                    kitty_pid = pid,
+                                    ?idle_timeout,
+                                    "Reaping idle kitty connection"
                                 );
                             }
                             to_remove.push(*pid);
@@ -92,11 +110,16 @@ impl KittyRegistry {
                     if let Some(conn) = connections.remove(pid) {
                         let mut client = conn.client.lock().await;
                         if let Err(e) = client.close().await {
-                            eprintln!("Error closing connection for PID {}: {}", pid, e);
+                            warn!(kitty_pid = pid, error = %e, "Error closing connection");
                         }
                     }
                     statuses.lock().await.remove(pid);
                 }
+
+                if !to_remove.is_empty() {
+                    let open = connections.lock().await.len();
+                    metrics().pool_connections.set(open as i64);
+                }
             }
         });
     }
@@ -105,22 +128,25 @@ impl KittyRegistry {
         &self,
         pid: i32,
     ) -> Result<ZoomingResult, Box<dyn std::error::Error>> {
-        self.execute_font_command(pid, true, 3).await
+        self.increase_font_size_by(pid, 3).await
     }
 
     pub async fn decrease_font_size(
         &self,
         pid: i32,
     ) -> Result<ZoomingResult, Box<dyn std::error::Error>> {
-        self.execute_font_command(pid, false, 3).await
+        self.decrease_font_size_by(pid, 3).await
     }
 
+    /// Grow the font by `amount` points, as an absolute size so the sanity
+    /// limits still apply
     pub async fn increase_font_size_by(
         &self,
         pid: i32,
         amount: u32,
     ) -> Result<ZoomingResult, Box<dyn std::error::Error>> {
-        self.execute_font_command(pid, true, amount).await
+        let size = self.current_font_size(pid) + f64::from(amount);
+        self.set_font_size(pid, size).await
     }
 
     pub async fn decrease_font_size_by(
@@ -128,7 +154,8 @@ impl KittyRegistry {
         pid: i32,
         amount: u32,
     ) -> Result<ZoomingResult, Box<dyn std::error::Error>> {
-        self.execute_font_command(pid, false, amount).await
+        let size = self.current_font_size(pid) - f64::from(amount);
+        self.set_font_size(pid, size).await
     }
 
     pub async fn multiply_font_size_by(
@@ -136,7 +163,8 @@ impl KittyRegistry {
         pid: i32,
         factor: f64,
     ) -> Result<ZoomingResult, Box<dyn std::error::Error>> {
-        self.execute_font_command_with_op(pid, "*", factor).await
+        let size = self.current_font_size(pid) * factor;
+        self.set_font_size(pid, size).await
     }
 
     pub async fn divide_font_size_by(
@@ -144,7 +172,47 @@ impl KittyRegistry {
         pid: i32,
         factor: f64,
     ) -> Result<ZoomingResult, Box<dyn std::error::Error>> {
-        self.execute_font_command_with_op(pid, "/", factor).await
+        let size = self.current_font_size(pid) / factor;
+        self.set_font_size(pid, size).await
+    }
+
+    /// The size last set in `pid`'s kitty through this registry, else
+    /// kitty.conf's `font_size`
+    fn current_font_size(&self, pid: i32) -> f64 {
+        self.font_sizes
+            .get(&pid)
+            .map(|size| *size)
+            .or_else(get_baseline_font_size)
+            .unwrap_or(DEFAULT_FONT_SIZE)
+    }
+
+    /// Set an absolute font size, as kitty's `set-font-size` without `+`/`-`
+    ///
+    /// Sizes outside `MIN_FONT_SIZE..=MAX_FONT_SIZE` are clamped whatever the
+    /// caller asked for; the result carries the size actually sent.
+    pub async fn set_font_size(
+        &self,
+        pid: i32,
+        size: f64,
+    ) -> Result<ZoomingResult, Box<dyn std::error::Error>> {
+        let applied = if size.is_nan() {
+            MIN_FONT_SIZE
+        } else {
+            size.clamp(MIN_FONT_SIZE, MAX_FONT_SIZE)
+        };
+        if applied != size {
+            warn!(
+                requested = size,
+                applied, "Font size outside sanity limits, clamping"
+            );
+        }
+
+        let mut result = self.execute_set_font_size(pid, applied).await;
+        if let Ok(ZoomingResult::Success { clamped, .. }) = &mut result {
+            *clamped = applied != size;
+            self.font_sizes.insert(pid, applied);
+        }
+        result
     }
 
     pub async fn cleanup_dead_connections(&self) {
@@ -161,215 +229,177 @@ impl KittyRegistry {
         }
 
         for pid in &to_remove {
-            eprintln!("Cleaning up dead PID {}", pid);
+            info!(kitty_pid = pid, "Cleaning up dead kitty connection");
             let mut connections = self.connections.lock().await;
             if let Some(conn) = connections.remove(pid) {
                 let mut client = conn.client.lock().await;
                 if let Err(e) = client.close().await {
-                    eprintln!("Error closing connection for PID {}: {}", pid, e);
+                    warn!(kitty_pid = pid, error = %e, "Error closing connection");
                 }
             }
             self.statuses.lock().await.remove(pid);
         }
+
+        self.record_pool_size().await;
     }
 
-    async fn execute_font_command(
+    #[instrument(
+        name = "kitty_command",
+        level = "debug",
+        skip(self),
+        fields(kitty_pid = tracing::field::Empty)
+    )]
+    async fn execute_set_font_size(
         &self,
         pid: i32,
-        increase: bool,
-        amount: u32,
+        size: f64,
     ) -> Result<ZoomingResult, Box<dyn std::error::Error>> {
-        let kitty_pid = if let Some(cached) = self.pid_cache.get(&pid) {
-            *cached
-        } else {
-            match crate::kitty::process::find_kitty_master_pid(pid) {
-                Some(kpid) => {
-                    self.pid_cache.insert(pid, kpid);
-                    kpid
-                }
-                None => {
-                    self.set_status(pid, KittyConnectionStatus::NoSocket).await;
-                    return Ok(ZoomingResult::NotConfigured);
-                }
-            }
-        };
-
-        let socket_path = get_kitty_socket_path(kitty_pid);
-
-        if !socket_path.exists() {
-            self.set_status(pid, KittyConnectionStatus::NoSocket).await;
-            return Ok(ZoomingResult::NotConfigured);
-        }
+        let result = self.try_set_font_size(pid, size).await;
+        record_result(&result);
+        result
+    }
 
-        if self.config.verbose {
-            eprintln!("Mapped shell PID {} to kitty master PID {}", pid, kitty_pid);
+    /// Send `set-font-size` through `request`, so retries and refusals are
+    /// handled like any other kitty command
+    async fn try_set_font_size(
+        &self,
+        pid: i32,
+        size: f64,
+    ) -> Result<ZoomingResult, Box<dyn std::error::Error>> {
+        let message = SetFontSizeCommand::builder()
+            .size(size)
+            .build()
+            .to_message()?;
+        if let Err(result) = self.request(pid, "=", message).await {
+            return Ok(result);
         }
 
-        let password = match get_kitty_password() {
-            Ok(pw) => pw,
-            Err(_) => {
-                self.set_status(kitty_pid, KittyConnectionStatus::NotConfigured)
-                    .await;
-                return Ok(ZoomingResult::NotConfigured);
-            }
-        };
-
-        let increment_op = if increase { "+" } else { "-" };
-
-        let mut last_error = None;
-
-        for attempt in 0..self.config.max_retries {
-            if attempt > 0 {
-                let delay = match attempt {
-                    1 => Duration::ZERO,
-                    2 => Duration::from_millis(100),
-                    _ => Duration::from_millis(200),
-                };
-                sleep(delay).await;
-            }
-
-            let client = match self
-                .get_or_create_connection(kitty_pid, &socket_path, &password)
-                .await
-            {
-                Ok(client) => client,
-                Err(e) => {
-                    last_error = Some(e.to_string());
-                    continue;
-                }
-            };
-
-            let mut all_succeeded = true;
-
-            for _count in 0..amount {
-                let cmd = SetFontSizeCommand::builder()
-                    .size(1.0)
-                    .increment_op(increment_op.to_string())
-                    .build()
-                    .to_message()?;
-
-                if self.config.verbose {
-                    eprintln!(
-                        "Sending command to PID {} (kitty: {}): {:?}",
-                        pid, kitty_pid, cmd
-                    );
-                }
+        Ok(ZoomingResult::Success {
+            pid,
+            font_adjustment: format!("={}", size),
+            font_size: Some(size),
+            clamped: false,
+        })
+    }
 
-                let mut client = client.lock().await;
-                let result = client.execute(&cmd).await;
+    /// Set the background opacity, as kitty's `set-background-opacity`
+    ///
+    /// kitty refuses unless kitty.conf sets `dynamic_background_opacity yes`.
+    pub async fn set_background_opacity(
+        &self,
+        pid: i32,
+        opacity: f64,
+    ) -> Result<ZoomingResult, Box<dyn std::error::Error>> {
+        let message = SetBackgroundOpacityCommand::builder()
+            .opacity(opacity as f32)
+            .build()
+            .to_message()?;
+        Ok(self
+            .send_command(pid, "set-background-opacity", message)
+            .await)
+    }
 
-                if self.config.verbose {
-                    eprintln!(
-                        "Font command result for PID {} (kitty: {}): {:?}",
-                        pid, kitty_pid, result
-                    );
-                }
-                match result {
-                    Ok(response) => {
-                        if !response.ok {
-                            all_succeeded = false;
-                            let error_msg = response
-                                .error
-                                .unwrap_or_else(|| "Unknown error".to_string());
-                            eprintln!(
-                                "Kitty returned error for PID {} (kitty: {}): {}",
-                                pid, kitty_pid, error_msg
-                            );
-                            last_error = Some(error_msg);
-                            break;
-                        }
-                    }
-                    Err(e) => {
-                        all_succeeded = false;
-                        last_error = Some(e.to_string());
-                        eprintln!(
-                            "Error executing font command for PID {} (kitty: {}): {}",
-                            pid, kitty_pid, e
-                        );
-                        break;
-                    }
-                }
-            }
+    /// Set colors in `pid`'s kitty, as `set-colors` with a theme's colors
+    pub async fn set_colors(
+        &self,
+        pid: i32,
+        colors: &Colors,
+    ) -> Result<ZoomingResult, Box<dyn std::error::Error>> {
+        let message = SetColorsCommand::builder()
+            .colors(colors.clone())
+            .build()
+            .to_message()?;
+        Ok(self.send_command(pid, "set-colors", message).await)
+    }
 
-            if all_succeeded {
-                self.update_last_used(kitty_pid).await;
-                self.set_status(pid, KittyConnectionStatus::Ready).await;
+    /// Give `pid`'s kitty its configured colors back, as `set-colors --reset`
+    pub async fn reset_colors(&self, pid: i32) -> ZoomingResult {
+        // kitty-rc refuses a set-colors without colors, which --reset needs
+        let message = CommandBuilder::new("set-colors")
+            .payload(serde_json::json!({ "colors": {}, "reset": true }))
+            .build();
+        self.send_command(pid, "set-colors", message).await
+    }
 
-                let font_adjustment = format!("{}{}", if increase { "+" } else { "-" }, amount);
-                return Ok(ZoomingResult::Success {
-                    pid,
-                    font_adjustment,
-                });
-            }
-        }
+    /// Set window padding and margin in points, as `set-spacing`
+    ///
+    /// `None` gives the window kitty.conf's `window_padding_width` or
+    /// `window_margin_width` back.
+    pub async fn set_spacing(
+        &self,
+        pid: i32,
+        padding: Option<f64>,
+        margin: Option<f64>,
+    ) -> Result<ZoomingResult, Box<dyn std::error::Error>> {
+        let message = SetSpacingCommand::builder()
+            .settings(spacing_settings(padding, margin))
+            .build()
+            .to_message()?;
+        Ok(self.send_command(pid, "set-spacing", message).await)
+    }
 
-        self.set_status(pid, KittyConnectionStatus::Failed).await;
+    /// The colors kitty.conf configures for `pid`'s kitty, as `get-colors --configured`
+    pub async fn configured_colors(&self, pid: i32) -> Option<Colors> {
+        let message = GetColorsCommand::builder()
+            .configured(true)
+            .build()
+            .to_message()
+            .ok()?;
+        let data = self.request(pid, "get-colors", message).await.ok()??;
+        Some(parse_colors(data.as_str()?))
+    }
 
-        if let Some(err) = last_error {
-            if err.contains("auth") || err.contains("password") {
-                return Ok(ZoomingResult::AuthFailed);
-            }
+    /// Send a command other than a font change to `pid`'s kitty
+    ///
+    /// Connection failures are retried like font commands. `name` labels the
+    /// command in logs, metrics and the result.
+    async fn send_command(
+        &self,
+        pid: i32,
+        name: &'static str,
+        message: KittyMessage,
+    ) -> ZoomingResult {
+        match self.request(pid, name, message).await {
+            Ok(_) => ZoomingResult::Success {
+                pid,
+                font_adjustment: name.to_string(),
+                font_size: None,
+                clamped: false,
+            },
+            Err(result) => result,
         }
-
-        Ok(ZoomingResult::ConnectionFailed)
     }
 
-    pub async fn execute_font_command_with_op(
+    /// Run `message` and return kitty's response data, or why it failed
+    async fn request(
         &self,
         pid: i32,
-        op: &str,
-        amount: f64,
-    ) -> Result<ZoomingResult, Box<dyn std::error::Error>> {
-        let kitty_pid = if let Some(cached) = self.pid_cache.get(&pid) {
-            *cached
-        } else {
-            match crate::kitty::process::find_kitty_master_pid(pid) {
-                Some(kpid) => {
-                    self.pid_cache.insert(pid, kpid);
-                    kpid
-                }
-                None => {
-                    self.set_status(pid, KittyConnectionStatus::NoSocket).await;
-                    return Ok(ZoomingResult::NotConfigured);
-                }
-            }
+        name: &str,
+        message: KittyMessage,
+    ) -> Result<Option<serde_json::Value>, ZoomingResult> {
+        let Some(kitty_pid) = self.kitty_pid(pid) else {
+            self.set_status(pid, KittyConnectionStatus::NoSocket).await;
+            return Err(ZoomingResult::NotConfigured);
         };
+        tracing::Span::current().record("kitty_pid", kitty_pid);
 
         let socket_path = get_kitty_socket_path(kitty_pid);
-
         if !socket_path.exists() {
             self.set_status(pid, KittyConnectionStatus::NoSocket).await;
-            return Ok(ZoomingResult::NotConfigured);
+            return Err(ZoomingResult::NotConfigured);
         }
 
-        if self.config.verbose {
-            eprintln!("Mapped shell PID {} to kitty master PID {}", pid, kitty_pid);
-        }
-
-        let password = match get_kitty_password() {
-            Ok(pw) => pw,
-            Err(_) => {
-                self.set_status(kitty_pid, KittyConnectionStatus::NotConfigured)
-                    .await;
-                return Ok(ZoomingResult::NotConfigured);
-            }
+        let Ok(password) = get_kitty_password() else {
+            self.set_status(kitty_pid, KittyConnectionStatus::NotConfigured)
+                .await;
+            return Err(ZoomingResult::NotConfigured);
         };
 
-        let increment_op = op;
-        let is_multiplicative = op == "*" || op == "/";
-        let iteration_count = if is_multiplicative { 1 } else { amount as u32 };
-        let cmd_size = if is_multiplicative { amount } else { 1.0 };
-
-        let mut last_error = None;
-
-        for attempt in 0..self.config.max_retries {
+        for attempt in 0..self.config().max_retries {
             if attempt > 0 {
-                let delay = match attempt {
-                    1 => Duration::ZERO,
-                    2 => Duration::from_millis(100),
-                    _ => Duration::from_millis(200),
-                };
-                sleep(delay).await;
+                debug!(attempt, command = name, "Retrying kitty command");
+                metrics().kitty_retries.inc();
+                sleep(retry_delay(attempt)).await;
             }
 
             let client = match self
@@ -378,84 +408,64 @@ impl KittyRegistry {
             {
                 Ok(client) => client,
                 Err(e) => {
-                    last_error = Some(e.to_string());
+                    debug!(kitty_pid, command = name, error = %e, "Failed to connect to kitty");
                     continue;
                 }
             };
 
-            let mut all_succeeded = true;
-
-            for _count in 0..iteration_count {
-                let cmd = SetFontSizeCommand::builder()
-                    .size(cmd_size)
-                    .increment_op(increment_op.to_string())
-                    .build()
-                    .to_message()?;
-
-                if self.config.verbose {
-                    eprintln!(
-                        "Sending command to PID {} (kitty: {}): {:?}",
-                        pid, kitty_pid, cmd
-                    );
-                }
-
-                let mut client = client.lock().await;
-                let result = client.execute(&cmd).await;
+            trace!(?message, "Sending command");
+            let result = client.lock().await.execute(&message).await;
+            let outcome = match &result {
+                Ok(response) if response.ok => "ok",
+                _ => "error",
+            };
+            metrics().kitty_commands.inc(&[name, outcome]);
 
-                if self.config.verbose {
-                    eprintln!(
-                        "Font command result for PID {} (kitty: {}): {:?}",
-                        pid, kitty_pid, result
-                    );
+            match result {
+                Ok(response) if response.ok => {
+                    self.update_last_used(kitty_pid).await;
+                    self.set_status(pid, KittyConnectionStatus::Ready).await;
+                    return Ok(response.data);
                 }
-                match result {
-                    Ok(response) => {
-                        if !response.ok {
-                            all_succeeded = false;
-                            let error_msg = response
-                                .error
-                                .unwrap_or_else(|| "Unknown error".to_string());
-                            eprintln!(
-                                "Kitty returned error for PID {} (kitty: {}): {}",
-                                pid, kitty_pid, error_msg
-                            );
-                            last_error = Some(error_msg);
-                            break;
-                        }
-                    }
-                    Err(e) => {
-                        all_succeeded = false;
-                        last_error = Some(e.to_string());
-                        eprintln!(
-                            "Error executing font command for PID {} (kitty: {}): {}",
-                            pid, kitty_pid, e
-                        );
-                        break;
+                // kitty understood the command and refused it; asking again won't help
+                Ok(response) => {
+                    let error = response
+                        .error
+                        .unwrap_or_else(|| "Unknown error".to_string());
+                    warn!(kitty_pid, command = name, error = %error, "Kitty returned error");
+                    if error.contains("auth") || error.contains("password") {
+                        return Err(ZoomingResult::AuthFailed);
                     }
+                    return Err(ZoomingResult::Failed);
+                }
+                Err(e) => {
+                    debug!(kitty_pid, command = name, error = %e, "Error executing kitty command");
                 }
-            }
-
-            if all_succeeded {
-                self.update_last_used(kitty_pid).await;
-                self.set_status(pid, KittyConnectionStatus::Ready).await;
-
-                let font_adjustment = format!("{}{}", op, amount);
-                return Ok(ZoomingResult::Success {
-                    pid,
-                    font_adjustment,
-                });
             }
         }
 
+        warn!(kitty_pid, command = name, "Giving up on kitty command");
         self.set_status(pid, KittyConnectionStatus::Failed).await;
+        Err(ZoomingResult::ConnectionFailed)
+    }
 
-        if let Some(err) = last_error {
-            if err.contains("auth") || err.contains("password") {
-                return Ok(ZoomingResult::AuthFailed);
-            }
-        }
+    /// Text columns of the active window in `pid`'s focused kitty OS window
+    ///
+    /// `None` if kitty cannot be reached or does not report columns.
+    pub async fn columns(&self, pid: i32) -> Option<u32> {
+        let message = LsCommand::builder().build().to_message().ok()?;
+        let data = self.request(pid, "ls", message).await.ok()??;
+        focused_columns(&parse_response_data(&data).ok()?)
+    }
 
-        Ok(ZoomingResult::ConnectionFailed)
+    /// kitty master PID for a window PID, cached after the first lookup
+    fn kitty_pid(&self, pid: i32) -> Option<i32> {
+        if let Some(cached) = self.pid_cache.get(&pid) {
+            return Some(*cached);
+        }
+        let kitty_pid = crate::kitty::process::find_kitty_master_pid(pid)?;
+        self.pid_cache.insert(pid, kitty_pid);
+        Some(kitty_pid)
     }
 
     async fn get_or_create_connection(
@@ -472,7 +482,7 @@ impl KittyRegistry {
                 return Ok(Arc::clone(&conn.client));
             }
 
-            if connections.len() >= self.config.max_connections {
+            if connections.len() >= self.config().max_connections {
                 let oldest_pid = connections
                     .iter()
                     .min_by_key(|(_, conn)| conn.last_used)
@@ -482,7 +492,7 @@ impl KittyRegistry {
                     if let Some(old_conn) = connections.remove(&old_pid) {
                         let mut client = old_conn.client.lock().await;
                         if let Err(e) = client.close().await {
-                            eprintln!("Error closing connection for PID {}: {}", old_pid, e);
+                            warn!(kitty_pid = old_pid, error = %e, "Error closing connection");
                         }
                     }
                     self.statuses.lock().await.remove(&old_pid);
@@ -490,28 +500,21 @@ impl KittyRegistry {
             }
         }
 
-        if self.config.verbose {
-            eprintln!(
-                "Connecting to kitty PID {} at socket: {:?}",
-                pid, socket_path
-            );
-        }
+        debug!(kitty_pid = pid, ?socket_path, "Connecting to kitty");
 
         let client = match Kitty::builder()
             .socket_path(socket_path)
-            .timeout(self.config.socket_timeout)
+            .timeout(self.config().socket_timeout)
             .password(password)
             .connect()
             .await
         {
             Ok(c) => {
-                if self.config.verbose {
-                    eprintln!("Successfully connected to kitty PID {}", pid);
-                }
+                debug!(kitty_pid = pid, "Connected to kitty");
                 c
             }
             Err(e) => {
-                eprintln!("Failed to connect to kitty PID {}: {}", pid, e);
+                warn!(kitty_pid = pid, error = %e, "Failed to connect to kitty");
                 self.set_status(pid, KittyConnectionStatus::Failed).await;
                 return Err(e.to_string());
             }
@@ -526,6 +529,7 @@ impl KittyRegistry {
                 last_used: Instant::now(),
             },
         );
+        metrics().pool_connections.set(connections.len() as i64);
 
         Ok(client_arc)
     }
@@ -538,15 +542,60 @@ impl KittyRegistry {
     }
 
     async fn set_status(&self, pid: i32, status: KittyConnectionStatus) {
-        self.statuses.lock().await.insert(pid, status);
+        let previous = self.statuses.lock().await.insert(pid, status.clone());
+        if previous.as_ref() != Some(&status) {
+            let from = previous.as_ref().map_or("unknown", |s| s.label());
+            metrics().status_transitions.inc(&[from, status.label()]);
+        }
+    }
+
+    async fn record_pool_size(&self) {
+        let open = self.connections.lock().await.len();
+        metrics().pool_connections.set(open as i64);
     }
 
     pub async fn get_status(&self, pid: i32) -> Option<KittyConnectionStatus> {
         self.statuses.lock().await.get(&pid).cloned()
     }
 
-    pub fn verbose(&self) -> bool {
-        self.config.verbose
+    /// Resolve a window PID to its kitty master PID if it has been seen before
+    pub fn cached_kitty_pid(&self, pid: i32) -> Option<i32> {
+        self.pid_cache.get(&pid).map(|kpid| *kpid)
+    }
+
+    /// Snapshot every kitty instance the registry knows about, keyed by kitty PID
+    ///
+    /// Statuses may be recorded against either the window PID or the kitty PID, so
+    /// both are folded onto the kitty PID through the PID cache.
+    pub async fn instances(&self) -> Vec<KittyInstanceStatus> {
+        let mut instances: BTreeMap<i32, KittyInstanceStatus> = BTreeMap::new();
+        let now = Instant::now();
+
+        for item in self.pid_cache.iter() {
+            instance_entry(&mut instances, *item.value())
+                .window_pids
+                .push(*item.key());
+        }
+
+        for (pid, conn) in self.connections.lock().await.iter() {
+            let instance = instance_entry(&mut instances, *pid);
+            instance.connected = true;
+            instance.idle_secs = Some(now.duration_since(conn.last_used).as_secs());
+        }
+
+        for (pid, status) in self.statuses.lock().await.iter() {
+            let kitty_pid = self.cached_kitty_pid(*pid).unwrap_or(*pid);
+            let instance = instance_entry(&mut instances, kitty_pid);
+            if instance.status.is_none() || *pid == kitty_pid {
+                instance.status = Some(status.clone());
+            }
+        }
+
+        for instance in instances.values_mut() {
+            instance.window_pids.sort_unstable();
+        }
+
+        instances.into_values().collect()
     }
 
     pub async fn shutdown(&self) {
@@ -555,10 +604,116 @@ impl KittyRegistry {
         for (pid, conn) in connections.drain() {
             let mut client = conn.client.lock().await;
             if let Err(e) = client.close().await {
-                eprintln!("Error closing connection for PID {}: {}", pid, e);
+                warn!(kitty_pid = pid, error = %e, "Error closing connection");
             }
         }
 
         self.statuses.lock().await.clear();
+        metrics().pool_connections.set(0);
+    }
+}
+
+fn instance_entry(
+    instances: &mut BTreeMap<i32, KittyInstanceStatus>,
+    kitty_pid: i32,
+) -> &mut KittyInstanceStatus {
+    instances
+        .entry(kitty_pid)
+        .or_insert_with(|| KittyInstanceStatus {
+            kitty_pid,
+            socket_path: get_kitty_socket_path(kitty_pid),
+            status: None,
+            connected: false,
+            idle_secs: None,
+            window_pids: Vec::new(),
+        })
+}
+
+/// Columns of the focused OS window's active tab, taking the widest window so a
+/// tab split side by side still counts its full width
+fn focused_columns(instances: &[OsInstance]) -> Option<u32> {
+    let os_window = instances
+        .iter()
+        .find(|w| w.is_focused == Some(true))
+        .or_else(|| instances.iter().find(|w| w.last_focused == Some(true)))
+        .or_else(|| instances.first())?;
+    let tab = os_window
+        .tabs
+        .iter()
+        .find(|t| t.is_active == Some(true))
+        .or_else(|| os_window.tabs.first())?;
+
+    let columns = tab.windows.iter().filter_map(|w| w.columns).max()?;
+    u32::try_from(columns).ok().filter(|&c| c > 0)
+}
+
+/// `set-spacing` settings for every edge; `null` means kitty.conf's value
+fn spacing_settings(
+    padding: Option<f64>,
+    margin: Option<f64>,
+) -> serde_json::Map<String, serde_json::Value> {
+    let mut settings = serde_json::Map::new();
+    for (kind, value) in [("padding", padding), ("margin", margin)] {
+        for edge in ["left", "top", "right", "bottom"] {
+            settings.insert(format!("{}_{}", kind, edge), serde_json::json!(value));
+        }
+    }
+    settings
+}
+
+/// Pause before retry `attempt` (1-based) of a kitty command
+fn retry_delay(attempt: u32) -> Duration {
+    match attempt {
+        1 => Duration::ZERO,
+        2 => Duration::from_millis(100),
+        _ => Duration::from_millis(200),
+    }
+}
+
+fn record_result(result: &Result<ZoomingResult, Box<dyn std::error::Error>>) {
+    let label = match result {
+        Ok(result) => result.label(),
+        Err(_) => "error",
+    };
+    metrics().zoom_results.inc(&[label]);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_focused_columns_uses_the_active_tab_of_the_focused_window() {
+        let instances: Vec<OsInstance> = serde_json::from_value(serde_json::json!([
+            {
+                "id": 1,
+                "is_focused": false,
+                "tabs": [{ "is_active": true, "windows": [{ "id": 1, "columns": 200 }] }]
+            },
+            {
+                "id": 2,
+                "is_focused": true,
+                "tabs": [
+                    { "is_active": false, "windows": [{ "id": 2, "columns": 300 }] },
+                    {
+                        "is_active": true,
+                        "windows": [{ "id": 3, "columns": 60 }, { "id": 4, "columns": 80 }]
+                    }
+                ]
+            }
+        ]))
+        .unwrap();
+
+        assert_eq!(focused_columns(&instances), Some(80));
+        assert_eq!(focused_columns(&[]), None);
+    }
+
+    #[test]
+    fn test_spacing_settings_cover_every_edge() {
+        let settings = spacing_settings(Some(20.0), None);
+        assert_eq!(settings.len(), 8);
+        assert_eq!(settings["padding_left"], 20.0);
+        assert_eq!(settings["padding_bottom"], 20.0);
+        assert!(settings["margin_top"].is_null());
     }
 }
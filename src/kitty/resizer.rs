@@ -1,49 +1,192 @@
-use crate::config::{ZoomConfig, ZoomType};
+use crate::config::Config;
+use crate::effects::kitty::{ColorsEffect, DimEffect, SpacingEffect};
+use crate::effects::{EffectResult, EffectsConfig, FocusEffect};
 use crate::kitty::KittyRegistry;
-use crate::kitty::conf_parser::get_baseline_font_size;
-use crate::niri::types::NiriEvent;
+use crate::kitty::appearance::AppearanceController;
+use crate::kitty::zoom::FontZoom;
+use crate::metrics::metrics;
+use crate::niri::types::{NiriEvent, OutputInfo, WindowInfo};
+use crate::reload::next_update;
+use crate::rules::{Rules, ZoomProfile};
 use dashmap::DashMap;
 use futures::{Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use tokio::sync::watch;
+use tracing::{Instrument, debug, info, info_span, warn};
 
-#[derive(Debug, Clone)]
-struct WindowState {
-    current_font_size: Option<f64>,
-    current_zoom_factor: f64,
+/// Tracked font state for one kitty PID, as reported by `status`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WindowStatus {
+    pub pid: i32,
+    pub font_size: Option<f64>,
+    pub zoom_factor: f64,
+    pub focused: bool,
+    pub window_ids: Vec<u64>,
+    pub profile: Option<String>,
 }
 
-impl WindowState {
-    fn new() -> Self {
-        Self {
-            current_font_size: None,
-            current_zoom_factor: 1.0,
-        }
+/// Read-only view of the resizer's per-window state, usable while it processes events
+#[derive(Clone)]
+pub struct ResizerHandle {
+    zoom: Arc<FontZoom>,
+    rules: Arc<RwLock<Rules>>,
+    windows: Arc<DashMap<u64, WindowInfo>>,
+}
+
+impl ResizerHandle {
+    /// The rules currently in effect, including any reloaded since startup
+    pub fn rules(&self) -> Rules {
+        self.rules.read().unwrap().clone()
     }
 
-    fn with_baseline() -> Self {
-        Self {
-            current_font_size: get_baseline_font_size(),
-            current_zoom_factor: 1.0,
+    /// Whether the current rules care about this window at all
+    pub fn considers(&self, window: &WindowInfo) -> bool {
+        self.rules.read().unwrap().considers(window)
+    }
+
+    /// Whether `event` should reach the resizer
+    ///
+    /// Blur and destroy events still pass for tracked windows the rules no
+    /// longer consider, so they are taken back to their baseline and forgotten.
+    pub fn wants(&self, event: &NiriEvent) -> bool {
+        match event {
+            NiriEvent::Blur { window_id, .. } | NiriEvent::Destroy { window_id }
+                if self.tracks(*window_id) =>
+            {
+                true
+            }
+            _ => event.window().is_none_or(|window| self.considers(window)),
         }
     }
+
+    /// Whether the resizer has seen this niri window and not yet forgotten it
+    pub fn tracks(&self, window_id: u64) -> bool {
+        self.windows.contains_key(&window_id)
+    }
+
+    /// The baseline font size windows are zoomed from, if kitty.conf sets one
+    pub fn baseline_font_size(&self) -> Option<f64> {
+        self.zoom.configured_baseline()
+    }
+
+    pub fn windows(&self) -> Vec<WindowStatus> {
+        let mut windows: Vec<WindowStatus> = self
+            .zoom
+            .window_states()
+            .iter()
+            .map(|entry| WindowStatus {
+                pid: *entry.key(),
+                font_size: entry.current_font_size,
+                zoom_factor: entry.current_zoom_factor,
+                focused: entry.focused,
+                window_ids: entry.window_ids.iter().copied().collect(),
+                profile: entry.profile.as_ref().map(|p| p.name.to_string()),
+            })
+            .collect();
+        windows.sort_by_key(|w| w.pid);
+        windows
+    }
 }
 
+/// Runs focus effects on tracked kitty windows as niri events arrive
+///
+/// Dim, colors, spacing and font zoom are `FocusEffect`s run in that order;
+/// `with_effect` appends more without touching the event loop.
 pub struct KittyResizer {
     kitty_registry: KittyRegistry,
-    zoom_config: ZoomConfig,
+    rules: Arc<RwLock<Rules>>,
+    zoom: Arc<FontZoom>,
+    effects: Vec<Arc<dyn FocusEffect>>,
+    config_updates: Option<watch::Receiver<Config>>,
+    baseline_updates: Option<watch::Receiver<Option<f64>>>,
+    focused_window: Option<WindowInfo>,
+    /// Last seen state of every kitty window with a PID, to re-resolve rules on
+    /// reload and to tell effects when a PID's last window closes
+    windows: Arc<DashMap<u64, WindowInfo>>,
+}
+
+enum Input {
+    Event(Option<NiriEvent>),
+    Reload(Box<Config>),
+    Baseline(Option<f64>),
 }
 
 impl KittyResizer {
     pub fn new(kitty_registry: KittyRegistry) -> Self {
+        Self::with_rules(kitty_registry, Rules::default())
+    }
+
+    pub fn with_rules(kitty_registry: KittyRegistry, rules: Rules) -> Self {
+        let zoom = FontZoom::new(Arc::new(kitty_registry.clone()));
+        Self::with_zoom(kitty_registry, rules, zoom)
+    }
+
+    /// Zoom with `zoom`, e.g. one changing font sizes through a fake `FontController`
+    pub fn with_zoom(kitty_registry: KittyRegistry, rules: Rules, zoom: FontZoom) -> Self {
+        let zoom = Arc::new(zoom);
+        let appearance: Arc<dyn AppearanceController> = Arc::new(kitty_registry.clone());
+        let effects: Vec<Arc<dyn FocusEffect>> = vec![
+            Arc::new(DimEffect::new(appearance.clone())),
+            Arc::new(ColorsEffect::new(appearance.clone())),
+            Arc::new(SpacingEffect::new(appearance)),
+            zoom.clone(),
+        ];
         Self {
             kitty_registry,
-            zoom_config: ZoomConfig::default(),
+            rules: Arc::new(RwLock::new(rules)),
+            zoom,
+            effects,
+            config_updates: None,
+            baseline_updates: None,
+            focused_window: None,
+            windows: Arc::new(DashMap::new()),
         }
     }
 
-    pub fn with_zoom_config(kitty_registry: KittyRegistry, zoom_config: ZoomConfig) -> Self {
-        Self {
-            kitty_registry,
-            zoom_config,
+    /// Apply the kitty side of `[effect]` alongside zoom
+    pub fn with_effects(self, effects: EffectsConfig) -> Self {
+        self.configure_effects(&effects);
+        self
+    }
+
+    /// Run `effect` after the built-in ones on every focus change
+    pub fn with_effect(mut self, effect: Arc<dyn FocusEffect>) -> Self {
+        self.effects.push(effect);
+        self
+    }
+
+    fn configure_effects(&self, effects: &EffectsConfig) {
+        for effect in &self.effects {
+            effect.configure(effects);
+        }
+    }
+
+    /// Apply configs published on `updates` while processing events
+    pub fn with_config_updates(mut self, updates: watch::Receiver<Config>) -> Self {
+        self.config_updates = Some(updates);
+        self
+    }
+
+    /// Restore windows to kitty.conf baselines published on `updates`
+    pub fn with_baseline_updates(mut self, updates: watch::Receiver<Option<f64>>) -> Self {
+        if let Some(baseline) = *updates.borrow() {
+            self.zoom.set_baseline(baseline);
+        }
+        self.baseline_updates = Some(updates);
+        self
+    }
+
+    pub fn registry(&self) -> &KittyRegistry {
+        &self.kitty_registry
+    }
+
+    pub fn handle(&self) -> ResizerHandle {
+        ResizerHandle {
+            zoom: Arc::clone(&self.zoom),
+            rules: Arc::clone(&self.rules),
+            windows: Arc::clone(&self.windows),
         }
     }
 
@@ -51,231 +194,571 @@ impl KittyResizer {
         &mut self,
         mut events: impl Stream<Item = NiriEvent> + std::marker::Send + std::marker::Unpin,
     ) -> Result<(), Box<dyn std::error::Error>> {
-        let window_states: DashMap<i32, WindowState> = DashMap::new();
+        loop {
+            let input = tokio::select! {
+                event = events.next() => Input::Event(event),
+                config = next_update(&mut self.config_updates) => Input::Reload(Box::new(config)),
+                baseline = next_update(&mut self.baseline_updates) => Input::Baseline(baseline),
+            };
+
+            let event = match input {
+                Input::Event(Some(event)) => event,
+                Input::Event(None) => break,
+                Input::Reload(config) => {
+                    self.apply_config(&config).await;
+                    continue;
+                }
+                Input::Baseline(baseline) => {
+                    self.apply_baseline(baseline).await;
+                    continue;
+                }
+            };
+
+            if let Some(window) = event.window()
+                && window.pid.is_some()
+            {
+                self.windows.insert(window.id, window.clone());
+            }
+
+            let span = info_span!(
+                "focus_event",
+                kind = event.kind(),
+                window_id = event.window_id(),
+                pid = event.window().and_then(|w| w.pid),
+            );
 
-        while let Some(event) = events.next().await {
             match event {
                 NiriEvent::Focus { window, .. } => {
-                    if let Some(pid) = window.pid {
-                        if let Some(zoom_type) = self.zoom_config.active_type() {
-                            let step_size = self.zoom_config.step_size;
-                            let mut window_state = window_states.entry(pid).or_insert_with(|| {
-                                WindowState::with_baseline()
-                            });
-
-                            let current_font = window_state.current_font_size
-                                .or(get_baseline_font_size())
-                                .unwrap_or(12.0);
-
-                            match zoom_type {
-                                ZoomType::Absolute => {
-                                    if let Some(target) = self.zoom_config.absolute {
-                                        if current_font < target {
-                                            let diff = target - current_font;
-                                            let steps = (diff / step_size).ceil() as u32;
-                                            let _ = self.kitty_registry
-                                                .increase_font_size_by(pid, steps * step_size as u32)
-                                                .await;
-                                            window_state.current_font_size = Some(target);
-                                            eprintln!(
-                                                "Kitty window {} gained focus (PID {}), setting absolute font size to {}",
-                                                window.id, pid, target
-                                            );
-                                        } else if current_font > target {
-                                            let diff = current_font - target;
-                                            let steps = (diff / step_size).ceil() as u32;
-                                            let _ = self.kitty_registry
-                                                .decrease_font_size_by(pid, steps * step_size as u32)
-                                                .await;
-                                            window_state.current_font_size = Some(target);
-                                            eprintln!(
-                                                "Kitty window {} gained focus (PID {}), setting absolute font size to {}",
-                                                window.id, pid, target
-                                            );
-                                        }
-                                    }
-                                }
-                                ZoomType::Additive => {
-                                    if let Some(amount) = self.zoom_config.additive {
-                                        let steps = (amount / step_size).ceil() as u32;
-                                        let _ = self.kitty_registry
-                                            .increase_font_size_by(pid, steps * step_size as u32)
-                                            .await;
-                                        window_state.current_font_size = Some(current_font + amount);
-                                        eprintln!(
-                                            "Kitty window {} gained focus (PID {}), increasing font by +{}",
-                                            window.id, pid, amount
-                                        );
-                                    }
-                                }
-                                ZoomType::Multiplicative => {
-                                    if let Some(factor) = self.zoom_config.multiplicative {
-                                        let baseline = get_baseline_font_size().unwrap_or(12.0);
-                                        let target_factor = factor;
-                                        let current_factor = window_state.current_zoom_factor;
-
-                                        if (target_factor - current_factor).abs() > 0.001 {
-                                            let multiply = target_factor > current_factor;
-                                            let op = if multiply { "*" } else { "/" };
-                                            let step_factor = step_size;
-
-                                            let mut zoom_factor = current_factor;
-                                            let mut steps_applied = 0;
-
-                                            while (multiply && zoom_factor < target_factor) || (!multiply && zoom_factor > target_factor) {
-                                                let next_factor = if multiply {
-                                                    zoom_factor * step_factor
-                                                } else {
-                                                    zoom_factor / step_factor
-                                                };
-
-                                                let should_apply = if multiply {
-                                                    next_factor <= target_factor
-                                                } else {
-                                                    next_factor >= target_factor
-                                                };
-
-                                                if should_apply {
-                                                    let _ = self.kitty_registry
-                                                        .execute_font_command_with_op(pid, op, step_factor)
-                                                        .await;
-                                                    zoom_factor = next_factor;
-                                                    steps_applied += 1;
-                                                } else {
-                                                    let final_factor = target_factor / zoom_factor;
-                                                    let _ = self.kitty_registry
-                                                        .execute_font_command_with_op(pid, op, final_factor)
-                                                        .await;
-                                                    steps_applied += 1;
-                                                    break;
-                                                }
-                                            }
-
-                                            window_state.current_zoom_factor = target_factor;
-                                            window_state.current_font_size = Some(baseline * target_factor);
-                                            eprintln!(
-                                                "Kitty window {} gained focus (PID {}), scaling from {:.2}x to {:.2}x ({} steps)",
-                                                window.id, pid, current_factor, target_factor, steps_applied
-                                            );
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
+                    self.handle_focus(&window).instrument(span).await;
+                    metrics().focus_applied(window.id);
+                    self.focused_window = Some(window);
                 }
                 NiriEvent::Blur { window, .. } => {
-                    if let Some(pid) = window.pid {
-                        if let Some(zoom_type) = self.zoom_config.active_type() {
-                            let step_size = self.zoom_config.step_size;
-                            let mut window_state = window_states.entry(pid).or_insert_with(|| {
-                                WindowState::with_baseline()
-                            });
-
-                            let current_font = window_state.current_font_size
-                                .or(get_baseline_font_size())
-                                .unwrap_or(12.0);
-
-                            match zoom_type {
-                                ZoomType::Absolute => {
-                                    let baseline = get_baseline_font_size().unwrap_or(12.0);
-                                    if current_font > baseline {
-                                        let diff = current_font - baseline;
-                                        let steps = (diff / step_size).ceil() as u32;
-                                        let _ = self.kitty_registry
-                                            .decrease_font_size_by(pid, steps * step_size as u32)
-                                            .await;
-                                        window_state.current_font_size = Some(baseline);
-                                        window_state.current_zoom_factor = 1.0;
-                                        eprintln!(
-                                            "Kitty window {} lost focus (PID {}), restoring baseline font size to {}",
-                                            window.id, pid, baseline
-                                        );
-                                    } else if current_font < baseline {
-                                        let diff = baseline - current_font;
-                                        let steps = (diff / step_size).ceil() as u32;
-                                        let _ = self.kitty_registry
-                                            .increase_font_size_by(pid, steps * step_size as u32)
-                                            .await;
-                                        window_state.current_font_size = Some(baseline);
-                                        window_state.current_zoom_factor = 1.0;
-                                        eprintln!(
-                                            "Kitty window {} lost focus (PID {}), restoring baseline font size to {}",
-                                            window.id, pid, baseline
-                                        );
-                                    }
-                                }
-                                ZoomType::Additive => {
-                                    if let Some(amount) = self.zoom_config.additive {
-                                        let steps = (amount / step_size).ceil() as u32;
-                                        let _ = self.kitty_registry
-                                            .decrease_font_size_by(pid, steps * step_size as u32)
-                                            .await;
-                                        window_state.current_font_size = Some(current_font - amount);
-                                        eprintln!(
-                                            "Kitty window {} lost focus (PID {}), decreasing font by -{}",
-                                            window.id, pid, amount
-                                        );
-                                    }
-                                }
-                                ZoomType::Multiplicative => {
-                                    if let Some(_factor) = self.zoom_config.multiplicative {
-                                        let target_factor = 1.0;
-                                        let current_factor = window_state.current_zoom_factor;
-
-                                        if (target_factor - current_factor).abs() > 0.001 {
-                                            let multiply = target_factor > current_factor;
-                                            let op = if multiply { "*" } else { "/" };
-                                            let step_factor = step_size;
-
-                                            let mut zoom_factor = current_factor;
-                                            let mut steps_applied = 0;
-
-                                            while (multiply && zoom_factor < target_factor) || (!multiply && zoom_factor > target_factor) {
-                                                let next_factor = if multiply {
-                                                    zoom_factor * step_factor
-                                                } else {
-                                                    zoom_factor / step_factor
-                                                };
-
-                                                let should_apply = if multiply {
-                                                    next_factor <= target_factor
-                                                } else {
-                                                    next_factor >= target_factor
-                                                };
-
-                                                if should_apply {
-                                                    let _ = self.kitty_registry
-                                                        .execute_font_command_with_op(pid, op, step_factor)
-                                                        .await;
-                                                    zoom_factor = next_factor;
-                                                    steps_applied += 1;
-                                                } else {
-                                                    let final_factor = target_factor / zoom_factor;
-                                                    let _ = self.kitty_registry
-                                                        .execute_font_command_with_op(pid, op, final_factor)
-                                                        .await;
-                                                    break;
-                                                }
-                                            }
-
-                                            window_state.current_zoom_factor = target_factor;
-                                            window_state.current_font_size = get_baseline_font_size();
-                                            eprintln!(
-                                                "Kitty window {} lost focus (PID {}), scaling from {:.2}x to {:.2}x ({} steps)",
-                                                window.id, pid, current_factor, target_factor, steps_applied
-                                            );
-                                        }
-                                    }
-                                }
-                            }
-                        }
+                    self.handle_blur(&window).instrument(span).await;
+                    if self.focused_window.as_ref().map(|w| w.id) == Some(window.id) {
+                        self.focused_window = None;
+                    }
+                }
+                NiriEvent::Moved { window, .. } => {
+                    if self.focused_window.as_ref().map(|w| w.id) == Some(window.id) {
+                        self.handle_focus(&window).instrument(span).await;
+                        self.focused_window = Some(window);
+                    } else {
+                        self.handle_blur(&window).instrument(span).await;
                     }
                 }
+                NiriEvent::Resized { window, .. }
+                    if self.focused_window.as_ref().map(|w| w.id) == Some(window.id) =>
+                {
+                    if self.fits_columns(&window) {
+                        self.handle_focus(&window).instrument(span).await;
+                    }
+                    self.focused_window = Some(window);
+                }
+                NiriEvent::Destroy { window_id } => {
+                    if self.focused_window.as_ref().map(|w| w.id) == Some(window_id) {
+                        self.focused_window = None;
+                    }
+                    span.in_scope(|| self.forget_window(window_id));
+                }
+                NiriEvent::OutputsChanged { outputs } => {
+                    self.apply_outputs(outputs).instrument(span).await;
+                }
                 _ => {}
             }
         }
 
         Ok(())
     }
+
+    /// Swap in reloaded rules, effects and pool settings
+    ///
+    /// Every tracked kitty is re-resolved against the new rules. The focused
+    /// window goes straight from its current size to its new focus target, so
+    /// it is not resized at all when that target is unchanged. Every other
+    /// window, and a focused one the new rules no longer zoom, is set to the
+    /// blur size of the profile the new rules pick.
+    async fn apply_config(&self, config: &Config) {
+        self.kitty_registry.set_config(config.to_registry_config());
+        *self.rules.write().unwrap() = Rules::from_config(config);
+        self.configure_effects(&config.effect);
+
+        let (zoomed, profiles) = {
+            let rules = self.rules.read().unwrap();
+            let zoomed = self
+                .focused_window
+                .as_ref()
+                .filter(|window| rules.resolve(window).is_some());
+            let zoomed_pid = zoomed.and_then(|w| w.pid);
+            let profiles: HashMap<i32, Option<ZoomProfile>> = self
+                .windows
+                .iter()
+                .filter_map(|entry| Some((entry.pid?, rules.resolve(entry.value()))))
+                .filter(|(pid, _)| Some(*pid) != zoomed_pid)
+                .collect();
+            (zoomed, profiles)
+        };
+        for (pid, profile) in profiles {
+            self.zoom.set_profile(pid, profile);
+        }
+
+        let zoomed_pid = zoomed.and_then(|w| w.pid);
+        if let Some(window) = &self.focused_window
+            && zoomed.is_none()
+        {
+            let span = info_span!("reload", window_id = window.id, pid = window.pid);
+            self.handle_blur(window).instrument(span).await;
+        }
+        report(
+            self.zoom.as_ref(),
+            self.zoom.blur_all(self.zoom.baseline(), zoomed_pid).await,
+        );
+        if let Some(window) = zoomed {
+            let span = info_span!("reload", window_id = window.id, pid = window.pid);
+            self.handle_focus(window).instrument(span).await;
+        }
+        self.restore_effects(false).await;
+
+        info!("Applied reloaded config");
+    }
+
+    /// Take every tracked window to a new kitty.conf baseline
+    async fn apply_baseline(&self, baseline: Option<f64>) {
+        let Some(baseline) = baseline else {
+            warn!("kitty.conf no longer sets font_size, keeping tracked font sizes");
+            return;
+        };
+        self.zoom.set_baseline(baseline);
+
+        let windows = self.resize_all(baseline, "rebaseline").await;
+        info!(
+            baseline,
+            windows, "Restored windows to the new baseline font size"
+        );
+    }
+
+    /// Swap in a new output list and resize windows for their outputs' new scale
+    async fn apply_outputs(&self, outputs: Vec<OutputInfo>) {
+        let count = outputs.len();
+        if !self.zoom.set_outputs(outputs) {
+            return;
+        }
+
+        let windows = self.resize_all(self.zoom.baseline(), "outputs").await;
+        info!(
+            outputs = count,
+            windows, "Re-applied zoom for changed outputs"
+        );
+    }
+
+    /// Recompute every tracked window's size and return how many there are
+    ///
+    /// Unfocused windows are set to their blur size outright; the focused window
+    /// goes through `handle_focus` so its rule and output are resolved afresh.
+    async fn resize_all(&self, baseline: f64, reason: &'static str) -> usize {
+        let focused_pid = self.focused_window.as_ref().and_then(|w| w.pid);
+        report(
+            self.zoom.as_ref(),
+            self.zoom.blur_all(baseline, focused_pid).await,
+        );
+
+        if let Some(window) = &self.focused_window {
+            let span = info_span!(
+                "resize_all",
+                reason,
+                window_id = window.id,
+                pid = window.pid
+            );
+            self.handle_focus(window).instrument(span).await;
+        }
+
+        self.zoom.window_states().len()
+    }
+
+    /// Whether the window's focus size follows its width
+    fn fits_columns(&self, window: &WindowInfo) -> bool {
+        let resolved = self.rules.read().unwrap().resolve(window);
+        resolved.is_some_and(|profile| self.zoom.fits_columns(window, &profile))
+    }
+
+    async fn handle_focus(&self, window: &WindowInfo) {
+        let Some(pid) = window.pid else {
+            return;
+        };
+        let resolved = self.rules.read().unwrap().resolve(window);
+        let Some(profile) = resolved else {
+            debug!("No rule zooms this window");
+            return;
+        };
+
+        for effect in &self.effects {
+            report(
+                effect.as_ref(),
+                effect.on_focus(window, pid, &profile).await,
+            );
+        }
+    }
+
+    async fn handle_blur(&self, window: &WindowInfo) {
+        let Some(pid) = window.pid else {
+            return;
+        };
+        let resolved = self.rules.read().unwrap().resolve(window);
+
+        for effect in &self.effects {
+            report(
+                effect.as_ref(),
+                effect.on_blur(window, pid, resolved.as_ref()).await,
+            );
+        }
+    }
+
+    /// Tell effects about a kitty PID once its last tracked window closed
+    fn forget_window(&mut self, window_id: u64) {
+        let Some(pid) = self
+            .windows
+            .remove(&window_id)
+            .and_then(|(_, window)| window.pid)
+        else {
+            return;
+        };
+        if self.windows.iter().any(|entry| entry.pid == Some(pid)) {
+            return;
+        }
+
+        debug!(pid, "Last tracked kitty window closed");
+        for effect in &self.effects {
+            effect.on_destroy(pid);
+        }
+    }
+
+    /// Let every effect undo what is no longer configured, or everything
+    async fn restore_effects(&self, everything: bool) {
+        for effect in &self.effects {
+            report(effect.as_ref(), effect.restore(everything).await);
+        }
+    }
+
+    /// Undo what the effects changed before the daemon exits
+    pub async fn shutdown(&self) {
+        self.restore_effects(true).await;
+        info!("Restored kitty font sizes, opacity, colors and spacing");
+    }
+}
+
+fn report(effect: &dyn FocusEffect, result: EffectResult) {
+    if let Err(e) = result {
+        warn!(effect = effect.name(), error = %e, "Focus effect failed");
+        metrics().effect_failures.inc(&[effect.name()]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{RegistryConfig, ZoomConfig, ZoomTarget};
+    use crate::kitty::font::FakeFonts;
+    use crate::rules::ZoomProfile;
+    use futures::future::BoxFuture;
+    use std::sync::Mutex;
+
+    /// Records every call, optionally failing each one
+    struct Recorder {
+        calls: Arc<Mutex<Vec<String>>>,
+        fail: bool,
+    }
+
+    impl Recorder {
+        fn record(&self, call: String) -> EffectResult {
+            self.calls.lock().unwrap().push(call);
+            if self.fail {
+                Err("failed on purpose".to_string())
+            } else {
+                Ok(())
+            }
+        }
+    }
+
+    impl FocusEffect for Recorder {
+        fn name(&self) -> &'static str {
+            "recorder"
+        }
+
+        fn on_focus<'a>(
+            &'a self,
+            window: &'a WindowInfo,
+            pid: i32,
+            profile: &'a ZoomProfile,
+        ) -> BoxFuture<'a, EffectResult> {
+            let call = format!("focus {} {} {}", window.id, pid, profile.name);
+            Box::pin(async move { self.record(call) })
+        }
+
+        fn on_blur<'a>(
+            &'a self,
+            window: &'a WindowInfo,
+            pid: i32,
+            profile: Option<&'a ZoomProfile>,
+        ) -> BoxFuture<'a, EffectResult> {
+            let call = format!("blur {} {} {}", window.id, pid, profile.is_some());
+            Box::pin(async move { self.record(call) })
+        }
+
+        fn on_destroy(&self, pid: i32) {
+            let _ = self.record(format!("destroy {}", pid));
+        }
+
+        fn restore(&self, everything: bool) -> BoxFuture<'_, EffectResult> {
+            Box::pin(async move { self.record(format!("restore {}", everything)) })
+        }
+    }
+
+    fn window(id: u64) -> WindowInfo {
+        WindowInfo {
+            id,
+            app_id: Some("kitty".to_string()),
+            pid: Some(100),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_effects_run_in_order_past_failures() {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let recorder = |fail| {
+            Arc::new(Recorder {
+                calls: Arc::clone(&calls),
+                fail,
+            })
+        };
+        let zoom = ZoomConfig {
+            additive: Some(4.0),
+            step_size: 4.0,
+            ..Default::default()
+        };
+        let rules = Rules::new("kitty".to_string(), zoom, Vec::new());
+        let fonts = Arc::new(FakeFonts::new(12.0));
+        let zoom = FontZoom::new(fonts.clone()).with_baseline(12.0);
+        let mut resizer =
+            KittyResizer::with_zoom(KittyRegistry::new(RegistryConfig::default()), rules, zoom)
+                .with_effect(recorder(true))
+                .with_effect(recorder(false));
+
+        let events = futures::stream::iter([
+            NiriEvent::Focus {
+                window_id: 1,
+                window: window(1),
+            },
+            NiriEvent::Blur {
+                window_id: 1,
+                window: window(1),
+            },
+            NiriEvent::Focus {
+                window_id: 2,
+                window: window(2),
+            },
+            NiriEvent::Destroy { window_id: 1 },
+            NiriEvent::Destroy { window_id: 2 },
+        ]);
+        resizer.process_events(events).await.unwrap();
+        resizer.shutdown().await;
+
+        let calls = calls.lock().unwrap();
+        let expected = [
+            "focus 1 100 default",
+            "blur 1 100 true",
+            "focus 2 100 default",
+            "destroy 100",
+            "restore true",
+        ];
+        let twice: Vec<&str> = expected.iter().flat_map(|call| [*call, *call]).collect();
+        assert_eq!(*calls, twice);
+        assert_eq!(fonts.sent(100), vec![16.0, 12.0, 16.0]);
+        assert!(resizer.handle().windows().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_baseline_updates_rebase_windows() {
+        let zoom = ZoomConfig {
+            additive: Some(4.0),
+            step_size: 4.0,
+            ..Default::default()
+        };
+        let rules = Rules::new("kitty".to_string(), zoom, Vec::new());
+        let fonts = Arc::new(FakeFonts::new(12.0));
+        let (baseline_tx, baseline_rx) = watch::channel(Some(12.0));
+        let mut resizer = KittyResizer::with_zoom(
+            KittyRegistry::new(RegistryConfig::default()),
+            rules,
+            FontZoom::new(fonts.clone()),
+        )
+        .with_baseline_updates(baseline_rx);
+        assert_eq!(resizer.handle().baseline_font_size(), Some(12.0));
+
+        let events = futures::stream::iter([NiriEvent::Focus {
+            window_id: 1,
+            window: window(1),
+        }]);
+        resizer.process_events(events).await.unwrap();
+
+        baseline_tx.send_replace(Some(14.0));
+        let baseline = next_update(&mut resizer.baseline_updates).await;
+        resizer.apply_baseline(baseline).await;
+
+        assert_eq!(fonts.sent(100), vec![16.0, 18.0]);
+        assert_eq!(resizer.handle().baseline_font_size(), Some(14.0));
+    }
+
+    #[tokio::test]
+    async fn test_tracked_windows_still_get_blur_and_destroy() {
+        let fonts = Arc::new(FakeFonts::new(12.0));
+        let mut resizer = KittyResizer::with_zoom(
+            KittyRegistry::new(RegistryConfig::default()),
+            Rules::new("kitty".to_string(), ZoomConfig::default(), Vec::new()),
+            FontZoom::new(fonts).with_baseline(12.0),
+        );
+        let events = futures::stream::iter([NiriEvent::Focus {
+            window_id: 1,
+            window: window(1),
+        }]);
+        resizer.process_events(events).await.unwrap();
+
+        // A reload stops considering kitty while window 1 is still zoomed
+        *resizer.rules.write().unwrap() =
+            Rules::new("foot".to_string(), ZoomConfig::default(), Vec::new());
+        let handle = resizer.handle();
+        let blur = |id| NiriEvent::Blur {
+            window_id: id,
+            window: window(id),
+        };
+
+        assert!(handle.wants(&blur(1)));
+        assert!(handle.wants(&NiriEvent::Destroy { window_id: 1 }));
+        assert!(!handle.wants(&blur(2)));
+        assert!(!handle.wants(&NiriEvent::Focus {
+            window_id: 1,
+            window: window(1),
+        }));
+    }
+
+    fn additive(amount: f64) -> Config {
+        Config {
+            zoom: ZoomConfig {
+                additive: Some(amount),
+                step_size: amount,
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reload_moves_the_focused_window_straight_to_its_new_target() {
+        let fonts = Arc::new(FakeFonts::new(12.0));
+        let config = additive(4.0);
+        let mut resizer = KittyResizer::with_zoom(
+            KittyRegistry::new(RegistryConfig::default()),
+            Rules::from_config(&config),
+            FontZoom::new(fonts.clone()).with_baseline(12.0),
+        );
+        let events = futures::stream::iter([NiriEvent::Focus {
+            window_id: 1,
+            window: window(1),
+        }]);
+        resizer.process_events(events).await.unwrap();
+
+        resizer.apply_config(&config).await;
+        assert_eq!(fonts.sent(100), vec![16.0]);
+
+        resizer.apply_config(&additive(8.0)).await;
+        assert_eq!(fonts.sent(100), vec![16.0, 20.0]);
+    }
+
+    #[tokio::test]
+    async fn test_reload_moves_unfocused_windows_to_their_new_blur_target() {
+        let blurred = |amount| Config {
+            zoom: ZoomConfig {
+                additive: Some(4.0),
+                blur: Some(ZoomTarget {
+                    additive: Some(amount),
+                    ..Default::default()
+                }),
+                step_size: 0.0,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let fonts = Arc::new(FakeFonts::new(12.0));
+        let config = blurred(-2.0);
+        let mut resizer = KittyResizer::with_zoom(
+            KittyRegistry::new(RegistryConfig::default()),
+            Rules::from_config(&config),
+            FontZoom::new(fonts.clone()).with_baseline(12.0),
+        );
+        let other = WindowInfo {
+            pid: Some(200),
+            ..window(2)
+        };
+        let events = futures::stream::iter([
+            NiriEvent::Focus {
+                window_id: 2,
+                window: other.clone(),
+            },
+            NiriEvent::Blur {
+                window_id: 2,
+                window: other,
+            },
+            NiriEvent::Focus {
+                window_id: 1,
+                window: window(1),
+            },
+        ]);
+        resizer.process_events(events).await.unwrap();
+        assert_eq!(fonts.sent(200), vec![16.0, 10.0]);
+
+        resizer.apply_config(&config).await;
+        assert_eq!(fonts.sent(200), vec![16.0, 10.0]);
+
+        resizer.apply_config(&blurred(-4.0)).await;
+        assert_eq!(fonts.sent(200), vec![16.0, 10.0, 8.0]);
+        assert_eq!(fonts.sent(100), vec![16.0]);
+
+        // Rules that no longer zoom kitty take it back to the baseline
+        resizer
+            .apply_config(&Config {
+                app_id: "foot".to_string(),
+                ..blurred(-4.0)
+            })
+            .await;
+        assert_eq!(fonts.sent(200), vec![16.0, 10.0, 8.0, 12.0]);
+        assert_eq!(fonts.sent(100), vec![16.0, 12.0]);
+    }
+
+    #[tokio::test]
+    async fn test_font_failures_are_reported_per_effect() {
+        let fonts = Arc::new(FakeFonts::new(12.0).failing());
+        let config = additive(4.0);
+        let mut resizer = KittyResizer::with_zoom(
+            KittyRegistry::new(RegistryConfig::default()),
+            Rules::from_config(&config),
+            FontZoom::new(fonts.clone()).with_baseline(12.0),
+        );
+        let failures = || metrics().effect_failures.get(&["zoom"]);
+        let before = failures();
+
+        let events = futures::stream::iter([
+            NiriEvent::Focus {
+                window_id: 1,
+                window: window(1),
+            },
+            NiriEvent::Focus {
+                window_id: 2,
+                window: window(2),
+            },
+        ]);
+        resizer.process_events(events).await.unwrap();
+
+        assert_eq!(failures() - before, 2);
+        assert!(fonts.sent(100).is_empty());
+    }
 }
@@ -1,6 +1,7 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum KittyConnectionStatus {
     Ready,
@@ -9,12 +10,57 @@ pub enum KittyConnectionStatus {
     Failed,
 }
 
+impl KittyConnectionStatus {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Ready => "ready",
+            Self::NoSocket => "no_socket",
+            Self::NotConfigured => "not_configured",
+            Self::Failed => "failed",
+        }
+    }
+}
+
+/// Pool view of one kitty instance, as reported by `status`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KittyInstanceStatus {
+    pub kitty_pid: i32,
+    pub socket_path: PathBuf,
+    pub status: Option<KittyConnectionStatus>,
+    pub connected: bool,
+    /// Seconds since the pooled connection was last used
+    pub idle_secs: Option<u64>,
+    /// Window PIDs reported by niri that map to this kitty instance
+    pub window_pids: Vec<i32>,
+}
+
 #[derive(Debug, Clone, Serialize)]
 #[serde(tag = "status", rename_all = "snake_case")]
 pub enum ZoomingResult {
-    Success { pid: i32, font_adjustment: String },
+    Success {
+        pid: i32,
+        font_adjustment: String,
+        /// Size kitty was set to, for absolute commands
+        #[serde(skip_serializing_if = "Option::is_none")]
+        font_size: Option<f64>,
+        /// The requested size was outside the registry's sanity limits
+        #[serde(skip_serializing_if = "std::ops::Not::not")]
+        clamped: bool,
+    },
     NotConfigured,
     ConnectionFailed,
     AuthFailed,
     Failed,
 }
+
+impl ZoomingResult {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Success { .. } => "success",
+            Self::NotConfigured => "not_configured",
+            Self::ConnectionFailed => "connection_failed",
+            Self::AuthFailed => "auth_failed",
+            Self::Failed => "failed",
+        }
+    }
+}
@@ -1,12 +1,15 @@
 use std::fs;
 use std::path::PathBuf;
 
+/// Location of the shared remote control password, `~/.config/kitty/rc.password`
+pub fn kitty_password_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("kitty/rc.password"))
+}
+
 pub fn get_kitty_password() -> Result<String, std::io::Error> {
-    let password_path = dirs::config_dir()
-        .ok_or_else(|| {
-            std::io::Error::new(std::io::ErrorKind::NotFound, "Config directory not found")
-        })?
-        .join("kitty/rc.password");
+    let password_path = kitty_password_path().ok_or_else(|| {
+        std::io::Error::new(std::io::ErrorKind::NotFound, "Config directory not found")
+    })?;
 
     if !password_path.exists() {
         return Err(std::io::Error::new(
@@ -43,3 +46,45 @@ pub fn get_kitty_socket_path(pid: i32) -> PathBuf {
 pub fn is_process_alive(pid: i32) -> bool {
     std::path::Path::new(&format!("/proc/{}", pid)).exists()
 }
+
+/// Running kitty instances that expose a `kitty-{pid}.sock` in the runtime directory
+pub fn find_kitty_instances() -> Vec<(i32, PathBuf)> {
+    let runtime_dir = std::env::var("XDG_RUNTIME_DIR").unwrap_or_else(|_| "/tmp".to_string());
+    let mut instances = Vec::new();
+
+    if let Ok(entries) = fs::read_dir(&runtime_dir) {
+        for entry in entries.flatten() {
+            if let Ok(name) = entry.file_name().into_string()
+                && let Some(pid_str) = name
+                    .strip_prefix("kitty-")
+                    .and_then(|s| s.strip_suffix(".sock"))
+                && let Ok(pid) = pid_str.parse::<i32>()
+                && is_process_alive(pid)
+            {
+                instances.push((pid, entry.path()));
+            }
+        }
+    }
+
+    instances.sort_by_key(|(pid, _)| *pid);
+    instances
+}
+
+/// PIDs of all running processes named `kitty`, with or without a socket
+pub fn find_kitty_processes() -> Vec<i32> {
+    let mut pids: Vec<i32> = fs::read_dir("/proc")
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter_map(|entry| entry.file_name().to_str()?.parse::<i32>().ok())
+                .filter(|pid| {
+                    fs::read_to_string(format!("/proc/{}/comm", pid))
+                        .is_ok_and(|comm| comm.trim() == "kitty")
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    pids.sort_unstable();
+    pids
+}
@@ -0,0 +1,770 @@
+use crate::config::{SizeContext, ZoomConfig, ZoomType};
+use crate::effects::{EffectResult, FocusEffect};
+use crate::kitty::ZoomingResult;
+use crate::kitty::conf_parser::get_baseline_font_size;
+use crate::kitty::font::FontController;
+use crate::kitty::registry::DEFAULT_FONT_SIZE;
+use crate::niri::actions::NiriActions;
+use crate::niri::types::{OutputInfo, WindowInfo};
+use crate::rules::ZoomProfile;
+use dashmap::DashMap;
+use futures::future::BoxFuture;
+use std::collections::{BTreeSet, HashMap};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio::task::AbortHandle;
+use tokio::time::{Instant, MissedTickBehavior};
+use tracing::{Instrument, debug, info, warn};
+
+#[derive(Debug, Clone)]
+pub(super) struct WindowState {
+    pub(super) current_font_size: Option<f64>,
+    pub(super) current_zoom_factor: f64,
+    pub(super) focused: bool,
+    pub(super) window_ids: BTreeSet<u64>,
+    /// Profile picked on the last focus; its blur target applies when focus leaves
+    pub(super) profile: Option<ZoomProfile>,
+    /// Animation still sending sizes to this window, if any
+    animation: Option<AbortHandle>,
+    /// Output the window was last seen on
+    output: Option<String>,
+    /// Window width in logical pixels, as niri last reported it
+    width: Option<f64>,
+    /// Logical pixels per text column per point, measured once for column targets
+    cell_width: Option<f64>,
+    /// Column width to restore on blur when `constant_grid` widened it
+    grid: Option<GridWidth>,
+}
+
+/// A window's width before constant-grid zoom changed it, and the font size it
+/// had at that width
+#[derive(Debug, Clone, Copy)]
+struct GridWidth {
+    window_id: u64,
+    width: f64,
+    font_size: f64,
+}
+
+impl WindowState {
+    fn with_font_size(current_font_size: Option<f64>) -> Self {
+        Self {
+            current_font_size,
+            current_zoom_factor: 1.0,
+            focused: false,
+            window_ids: BTreeSet::new(),
+            profile: None,
+            animation: None,
+            output: None,
+            width: None,
+            cell_width: None,
+            grid: None,
+        }
+    }
+}
+
+/// Font zoom as a focus effect: each profile's `[zoom]` sizes on focus and blur
+pub struct FontZoom {
+    fonts: Arc<dyn FontController>,
+    niri_actions: NiriActions,
+    window_states: Arc<DashMap<i32, WindowState>>,
+    outputs: RwLock<HashMap<String, OutputInfo>>,
+    /// Baseline last set by the resizer; kitty.conf's `font_size` until then
+    baseline: RwLock<Option<f64>>,
+}
+
+impl FontZoom {
+    pub fn new(fonts: Arc<dyn FontController>) -> Self {
+        Self {
+            fonts,
+            niri_actions: NiriActions::new(),
+            window_states: Arc::new(DashMap::new()),
+            outputs: RwLock::new(HashMap::new()),
+            baseline: RwLock::new(None),
+        }
+    }
+
+    /// Zoom from `baseline` instead of kitty.conf's `font_size`
+    pub fn with_baseline(self, baseline: f64) -> Self {
+        self.set_baseline(baseline);
+        self
+    }
+
+    /// Zoom from `baseline` from now on, e.g. after kitty.conf changed
+    pub fn set_baseline(&self, baseline: f64) {
+        *self.baseline.write().unwrap() = Some(baseline);
+    }
+
+    /// kitty.conf `font_size`, or `DEFAULT_FONT_SIZE` when it is not set
+    pub fn baseline(&self) -> f64 {
+        self.configured_baseline().unwrap_or(DEFAULT_FONT_SIZE)
+    }
+
+    pub(super) fn configured_baseline(&self) -> Option<f64> {
+        let baseline = *self.baseline.read().unwrap();
+        baseline.or_else(get_baseline_font_size)
+    }
+
+    fn new_state(&self) -> WindowState {
+        WindowState::with_font_size(self.configured_baseline())
+    }
+
+    pub(super) fn window_states(&self) -> Arc<DashMap<i32, WindowState>> {
+        Arc::clone(&self.window_states)
+    }
+
+    /// Swap in a new output list and return whether it differs from the last one
+    pub fn set_outputs(&self, outputs: Vec<OutputInfo>) -> bool {
+        let outputs: HashMap<String, OutputInfo> = outputs
+            .into_iter()
+            .map(|output| (output.name.clone(), output))
+            .collect();
+        let mut current = self.outputs.write().unwrap();
+        if *current == outputs {
+            return false;
+        }
+        *current = outputs;
+        true
+    }
+
+    /// Whether the window's focus size under `profile` follows its width
+    pub fn fits_columns(&self, window: &WindowInfo, profile: &ZoomProfile) -> bool {
+        let output = self.output(window.output.as_deref());
+        profile.zoom.focus_target_on(output.as_ref()).active_type() == Some(ZoomType::Columns)
+    }
+
+    /// Blur `pid` with `profile` from now on, e.g. after a reload changed its rule
+    ///
+    /// `None` means no rule zooms it any more, so `blur_all` takes it to the baseline.
+    pub fn set_profile(&self, pid: i32, profile: Option<ZoomProfile>) {
+        if let Some(mut window_state) = self.window_states.get_mut(&pid) {
+            window_state.profile = profile;
+        }
+    }
+
+    /// Set every tracked window but `focused_pid` to its blur size
+    ///
+    /// Windows already at their blur size are left alone. A failed window does
+    /// not stop the others; the first failure is returned.
+    pub async fn blur_all(&self, baseline: f64, focused_pid: Option<i32>) -> EffectResult {
+        let pids: Vec<i32> = self
+            .window_states
+            .iter()
+            .map(|entry| *entry.key())
+            .filter(|&pid| Some(pid) != focused_pid)
+            .collect();
+
+        let mut result = Ok(());
+        for pid in pids {
+            let profile = self
+                .window_states
+                .get(&pid)
+                .and_then(|state| state.profile.clone());
+            let target = profile.map_or(baseline, |p| {
+                self.with_size_context(pid, baseline, |context| p.zoom.blur_size(context))
+            });
+            self.cancel_animation(pid);
+            let from = self.current_font_size(pid, baseline);
+            if (target - from).abs() < f64::EPSILON {
+                continue;
+            }
+            result = result.and(self.resize(pid, &[target], baseline).await);
+        }
+
+        result
+    }
+
+    fn output(&self, name: Option<&str>) -> Option<OutputInfo> {
+        name.and_then(|name| self.outputs.read().unwrap().get(name).cloned())
+    }
+
+    /// Call `f` with everything sizes depend on for `pid`, from its tracked state
+    fn with_size_context<R>(
+        &self,
+        pid: i32,
+        baseline: f64,
+        f: impl FnOnce(&SizeContext<'_>) -> R,
+    ) -> R {
+        let (output, width, cell_width) = match self.window_states.get(&pid) {
+            Some(state) => (
+                self.output(state.output.as_deref()),
+                state.width,
+                state.cell_width,
+            ),
+            None => (None, None, None),
+        };
+        f(&SizeContext {
+            baseline,
+            output: output.as_ref(),
+            width,
+            cell_width,
+        })
+    }
+
+    /// Work out how wide a text column is per point of font size, once per window
+    ///
+    /// kitty reports how many columns it shows at the size we last set, and
+    /// niri how wide the window is; cell width scales linearly with font size.
+    async fn measure_cell_width(&self, pid: i32, baseline: f64) {
+        let width = match self.window_states.get(&pid) {
+            Some(state) if state.cell_width.is_none() => state.width,
+            _ => return,
+        };
+        let Some(width) = width else {
+            return;
+        };
+
+        self.cancel_animation(pid);
+        let Some(columns) = self.fonts.columns(pid).await else {
+            debug!("kitty did not report its columns, keeping the baseline");
+            return;
+        };
+
+        let size = self.current_font_size(pid, baseline);
+        let cell_width = width / (f64::from(columns) * size);
+        debug!(
+            columns,
+            width, size, cell_width, "Measured kitty cell width"
+        );
+        if let Some(mut window_state) = self.window_states.get_mut(&pid) {
+            window_state.cell_width = Some(cell_width);
+        }
+    }
+
+    async fn focus(&self, window: &WindowInfo, pid: i32, profile: &ZoomProfile) -> EffectResult {
+        if !profile.zoom.is_configured() {
+            return Ok(());
+        }
+
+        {
+            let mut window_state = self
+                .window_states
+                .entry(pid)
+                .or_insert_with(|| self.new_state());
+            window_state.window_ids.insert(window.id);
+            window_state.focused = true;
+            window_state.output = window.output.clone();
+            window_state.width = window.window_size.map(|(width, _)| f64::from(width));
+            window_state.profile = Some(profile.clone());
+        }
+        debug!(profile = %profile.name, "Applying zoom profile");
+
+        let baseline = self.baseline();
+        let output = self.output(window.output.as_deref());
+        let kind = profile.zoom.focus_target_on(output.as_ref()).active_type();
+        if kind == Some(ZoomType::Columns) {
+            self.measure_cell_width(pid, baseline).await;
+        }
+
+        let Some(target) =
+            self.with_size_context(pid, baseline, |context| profile.zoom.focus_size(context))
+        else {
+            return Ok(());
+        };
+        let from = self
+            .transition(pid, &profile.zoom, target, kind, baseline)
+            .await?;
+        if profile.zoom.constant_grid {
+            self.keep_grid(pid, window, from, target).await?;
+        }
+
+        info!(
+            from,
+            to = target,
+            profile = %profile.name,
+            "Kitty window gained focus, zooming"
+        );
+        Ok(())
+    }
+
+    async fn blur(&self, window: &WindowInfo, pid: i32) -> EffectResult {
+        let (profile, grid) = {
+            let mut window_state = self
+                .window_states
+                .entry(pid)
+                .or_insert_with(|| self.new_state());
+            window_state.window_ids.insert(window.id);
+            window_state.focused = false;
+            window_state.output = window.output.clone();
+            window_state.width = window.window_size.map(|(width, _)| f64::from(width));
+            (window_state.profile.clone(), window_state.grid.take())
+        };
+
+        // The font still goes back if niri refuses the width
+        let restored = match grid {
+            Some(grid) => self.restore_width(grid).await,
+            None => Ok(()),
+        };
+
+        let Some(profile) = profile else {
+            return restored;
+        };
+
+        let baseline = self.baseline();
+        let target =
+            self.with_size_context(pid, baseline, |context| profile.zoom.blur_size(context));
+        let from = self
+            .transition(
+                pid,
+                &profile.zoom,
+                target,
+                profile.zoom.blur_type(),
+                baseline,
+            )
+            .await?;
+
+        info!(
+            from,
+            to = target,
+            profile = %profile.name,
+            "Kitty window lost focus, applying blur size"
+        );
+        restored
+    }
+
+    /// Take every tracked window back to the kitty.conf font size and its
+    /// original column width
+    async fn restore_baseline(&self) -> EffectResult {
+        let baseline = self.baseline();
+        let pids: Vec<i32> = self
+            .window_states
+            .iter()
+            .map(|entry| *entry.key())
+            .collect();
+
+        let mut result = Ok(());
+        for pid in pids {
+            self.cancel_animation(pid);
+            let grid = self
+                .window_states
+                .get_mut(&pid)
+                .and_then(|mut state| state.grid.take());
+            if let Some(grid) = grid {
+                result = result.and(self.restore_width(grid).await);
+            }
+
+            let from = self.current_font_size(pid, baseline);
+            if (from - baseline).abs() >= f64::EPSILON {
+                result = result.and(self.resize(pid, &[baseline], baseline).await);
+            }
+        }
+        result
+    }
+
+    async fn restore_width(&self, grid: GridWidth) -> EffectResult {
+        debug!(width = grid.width, "Restoring column width");
+        self.set_window_width(grid.window_id, grid.width.round() as i32)
+            .await
+    }
+
+    async fn set_window_width(&self, window_id: u64, width: i32) -> EffectResult {
+        self.niri_actions
+            .set_window_width(window_id, width)
+            .await
+            .map_err(|e| format!("Failed to set window width: {}", e))
+    }
+
+    /// Scale the window's column so its text grid keeps its size at `target`
+    ///
+    /// The width and font size from before the first zoom are kept until blur,
+    /// so repeated zooms scale from the original instead of compounding.
+    async fn keep_grid(
+        &self,
+        pid: i32,
+        window: &WindowInfo,
+        from: f64,
+        target: f64,
+    ) -> EffectResult {
+        let Some((width, _)) = window.window_size else {
+            return Ok(());
+        };
+
+        let grid = {
+            let Some(mut window_state) = self.window_states.get_mut(&pid) else {
+                return Ok(());
+            };
+            match window_state.grid {
+                Some(grid) if grid.window_id == window.id => grid,
+                _ => *window_state.grid.insert(GridWidth {
+                    window_id: window.id,
+                    width: f64::from(width),
+                    font_size: from,
+                }),
+            }
+        };
+
+        let new_width = (grid.width * target / grid.font_size).round() as i32;
+        if new_width != width {
+            debug!(
+                from = width,
+                to = new_width,
+                "Scaling column width with the font"
+            );
+            self.set_window_width(window.id, new_width).await?;
+        }
+        Ok(())
+    }
+
+    fn current_font_size(&self, pid: i32, baseline: f64) -> f64 {
+        self.window_states
+            .get(&pid)
+            .and_then(|state| state.current_font_size)
+            .unwrap_or(baseline)
+    }
+
+    /// Move `pid` from its tracked size to `target` and return where it started
+    ///
+    /// An animation still running for the window is cancelled first, so the new
+    /// transition starts from the last size it reached. With `[zoom.animation]`
+    /// the sizes are sent in the background; otherwise in `step_size` steps.
+    async fn transition(
+        &self,
+        pid: i32,
+        zoom: &ZoomConfig,
+        target: f64,
+        kind: Option<ZoomType>,
+        baseline: f64,
+    ) -> Result<f64, String> {
+        self.cancel_animation(pid);
+        let from = self.current_font_size(pid, baseline);
+
+        match &zoom.animation {
+            Some(animation) if (target - from).abs() >= f64::EPSILON => {
+                self.animate(
+                    pid,
+                    animation.sizes(from, target),
+                    animation.frame_interval(),
+                    baseline,
+                );
+            }
+            _ => {
+                self.resize(pid, &zoom.steps(from, target, kind), baseline)
+                    .await?;
+            }
+        }
+
+        Ok(from)
+    }
+
+    /// Send `sizes` one `interval` apart from a background task
+    ///
+    /// Each confirmed size is recorded as it lands; the first size kitty does
+    /// not apply ends the animation. Nothing awaits the task, so failures are
+    /// only logged.
+    fn animate(&self, pid: i32, sizes: Vec<f64>, interval: Duration, baseline: f64) {
+        let fonts = Arc::clone(&self.fonts);
+        let window_states = Arc::clone(&self.window_states);
+
+        let task = tokio::spawn(
+            async move {
+                let mut ticker = tokio::time::interval_at(Instant::now() + interval, interval);
+                ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+                for size in sizes {
+                    ticker.tick().await;
+                    match send_size(fonts.as_ref(), pid, size).await {
+                        Ok(Some(applied)) => {
+                            record_font_size(&window_states, pid, applied, baseline)
+                        }
+                        Ok(None) => break,
+                        Err(e) => {
+                            warn!(error = %e, "Font animation failed");
+                            break;
+                        }
+                    }
+                }
+            }
+            .in_current_span(),
+        );
+
+        if let Some(mut window_state) = self.window_states.get_mut(&pid) {
+            window_state.animation = Some(task.abort_handle());
+        }
+    }
+
+    fn cancel_animation(&self, pid: i32) {
+        if let Some(animation) = self
+            .window_states
+            .get_mut(&pid)
+            .and_then(|mut state| state.animation.take())
+        {
+            animation.abort();
+        }
+    }
+
+    /// Send each size in turn, recording every one kitty confirms
+    ///
+    /// Stops at the first command that does not succeed, so the tracked size
+    /// matches what the window actually shows.
+    async fn resize(&self, pid: i32, sizes: &[f64], baseline: f64) -> EffectResult {
+        for &size in sizes {
+            match send_size(self.fonts.as_ref(), pid, size).await? {
+                Some(applied) => record_font_size(&self.window_states, pid, applied, baseline),
+                None => break,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FocusEffect for FontZoom {
+    fn name(&self) -> &'static str {
+        "zoom"
+    }
+
+    fn on_focus<'a>(
+        &'a self,
+        window: &'a WindowInfo,
+        pid: i32,
+        profile: &'a ZoomProfile,
+    ) -> BoxFuture<'a, EffectResult> {
+        Box::pin(self.focus(window, pid, profile))
+    }
+
+    /// Blur uses the profile the window was zoomed with, whatever rules say now
+    fn on_blur<'a>(
+        &'a self,
+        window: &'a WindowInfo,
+        pid: i32,
+        _profile: Option<&'a ZoomProfile>,
+    ) -> BoxFuture<'a, EffectResult> {
+        Box::pin(self.blur(window, pid))
+    }
+
+    fn on_destroy(&self, pid: i32) {
+        self.cancel_animation(pid);
+        self.window_states.remove(&pid);
+    }
+
+    /// Zoom follows rules rather than `[effect]`, so only `everything` undoes it
+    fn restore(&self, everything: bool) -> BoxFuture<'_, EffectResult> {
+        Box::pin(async move {
+            if everything {
+                return self.restore_baseline().await;
+            }
+            Ok(())
+        })
+    }
+}
+
+/// Set one absolute size and return what kitty applied
+///
+/// `None` if kitty is not set up for remote control; a refused or failed
+/// command is an error.
+async fn send_size(fonts: &dyn FontController, pid: i32, size: f64) -> Result<Option<f64>, String> {
+    match fonts.set_font_size(pid, size).await {
+        Ok(ZoomingResult::Success {
+            font_size: Some(applied),
+            clamped,
+            ..
+        }) => {
+            if clamped {
+                warn!(requested = size, applied, "Font size was clamped");
+            }
+            Ok(Some(applied))
+        }
+        Ok(result @ (ZoomingResult::Success { .. } | ZoomingResult::NotConfigured)) => {
+            debug!(result = result.label(), "Font size not changed");
+            Ok(None)
+        }
+        Ok(result) => Err(format!("Failed to set font size: {}", result.label())),
+        Err(e) => Err(format!("Failed to set font size: {}", e)),
+    }
+}
+
+fn record_font_size(window_states: &DashMap<i32, WindowState>, pid: i32, size: f64, baseline: f64) {
+    if let Some(mut window_state) = window_states.get_mut(&pid) {
+        window_state.current_font_size = Some(size);
+        window_state.current_zoom_factor = size / baseline;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kitty::font::FakeFonts;
+    use proptest::prelude::*;
+
+    const PID: i32 = 100;
+
+    fn window(id: u64, pid: i32) -> WindowInfo {
+        WindowInfo {
+            id,
+            pid: Some(pid),
+            window_size: Some((1000, 600)),
+            ..Default::default()
+        }
+    }
+
+    fn profile(zoom: ZoomConfig) -> ZoomProfile {
+        ZoomProfile {
+            name: Arc::from("default"),
+            zoom,
+        }
+    }
+
+    fn zoom(fonts: &Arc<FakeFonts>, baseline: f64) -> FontZoom {
+        FontZoom::new(fonts.clone()).with_baseline(baseline)
+    }
+
+    fn assert_sizes(actual: &[f64], expected: &[f64]) {
+        assert_eq!(actual.len(), expected.len(), "{:?}", actual);
+        for (actual, expected) in actual.iter().zip(expected) {
+            assert!((actual - expected).abs() < 1e-9, "{:?}", actual);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_multiplicative_zoom_steps_by_factor_both_ways() {
+        let fonts = Arc::new(FakeFonts::new(10.0));
+        let zoom = zoom(&fonts, 10.0);
+        let profile = profile(ZoomConfig {
+            multiplicative: Some(2.0),
+            step_size: 1.25,
+            ..Default::default()
+        });
+        let window = window(1, PID);
+
+        zoom.on_focus(&window, PID, &profile).await.unwrap();
+        assert_sizes(&fonts.sent(PID), &[12.5, 15.625, 19.53125, 20.0]);
+
+        zoom.on_blur(&window, PID, Some(&profile)).await.unwrap();
+        assert_sizes(&fonts.sent(PID)[4..], &[16.0, 12.8, 10.24, 10.0]);
+        assert_eq!(
+            zoom.window_states.get(&PID).unwrap().current_zoom_factor,
+            1.0
+        );
+    }
+
+    #[tokio::test]
+    async fn test_additive_zoom_rounds_steps_to_the_target() {
+        let fonts = Arc::new(FakeFonts::new(12.0));
+        let zoom = zoom(&fonts, 12.0);
+        let profile = profile(ZoomConfig {
+            additive: Some(5.0),
+            step_size: 2.0,
+            ..Default::default()
+        });
+        let window = window(1, PID);
+
+        zoom.on_focus(&window, PID, &profile).await.unwrap();
+        assert_sizes(&fonts.sent(PID), &[14.0, 16.0, 17.0]);
+
+        // Focusing again from the zoomed size sends nothing
+        zoom.on_focus(&window, PID, &profile).await.unwrap();
+        assert_eq!(fonts.sent(PID).len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_clamped_zoom_still_returns_to_baseline() {
+        let fonts = Arc::new(FakeFonts::new(12.0));
+        let zoom = zoom(&fonts, 12.0);
+        let profile = profile(ZoomConfig {
+            absolute: Some(500.0),
+            step_size: 0.0,
+            ..Default::default()
+        });
+        let window = window(1, PID);
+
+        zoom.on_focus(&window, PID, &profile).await.unwrap();
+        assert_eq!(fonts.size(PID), 200.0);
+        let state = zoom.window_states.get(&PID).unwrap().clone();
+        assert_eq!(state.current_font_size, Some(200.0));
+
+        zoom.on_blur(&window, PID, Some(&profile)).await.unwrap();
+        assert_eq!(fonts.size(PID), 12.0);
+    }
+
+    #[tokio::test]
+    async fn test_columns_zoom_measures_cell_width() {
+        // 100 columns at 10pt across the 1000px window: 1px per column per point
+        let fonts = Arc::new(FakeFonts::new(10.0).with_columns(100, 10.0));
+        let zoom = zoom(&fonts, 10.0);
+        let profile = profile(ZoomConfig {
+            columns: Some(80),
+            step_size: 0.0,
+            ..Default::default()
+        });
+
+        zoom.on_focus(&window(1, PID), PID, &profile).await.unwrap();
+        assert_eq!(fonts.size(PID), 12.5);
+    }
+
+    #[tokio::test]
+    async fn test_restore_takes_windows_back_to_baseline() {
+        let fonts = Arc::new(FakeFonts::new(11.0));
+        let zoom = zoom(&fonts, 11.0);
+        let profile = profile(ZoomConfig {
+            absolute: Some(16.0),
+            ..Default::default()
+        });
+        zoom.on_focus(&window(1, PID), PID, &profile).await.unwrap();
+        zoom.on_focus(&window(2, PID + 1), PID + 1, &profile)
+            .await
+            .unwrap();
+
+        zoom.restore(false).await.unwrap();
+        assert_eq!(fonts.size(PID), 16.0);
+
+        zoom.restore(true).await.unwrap();
+        assert_eq!(fonts.size(PID), 11.0);
+        assert_eq!(fonts.size(PID + 1), 11.0);
+    }
+
+    fn zoom_config() -> impl Strategy<Value = ZoomConfig> {
+        let target = prop_oneof![
+            (1.0..100.0f64).prop_map(|size| ZoomConfig {
+                absolute: Some(size),
+                ..Default::default()
+            }),
+            (-20.0..40.0f64).prop_map(|amount| ZoomConfig {
+                additive: Some(amount),
+                ..Default::default()
+            }),
+            (0.25..4.0f64).prop_map(|factor| ZoomConfig {
+                multiplicative: Some(factor),
+                ..Default::default()
+            }),
+        ];
+        (target, 0.0..3.0f64).prop_map(|(zoom, step_size)| ZoomConfig { step_size, ..zoom })
+    }
+
+    proptest! {
+        #[test]
+        fn prop_focus_blur_round_trips_return_to_baseline(
+            baseline in 6.0..30.0f64,
+            zoom_config in zoom_config(),
+            focus in proptest::collection::vec((0..3u64, any::<bool>()), 1..12),
+        ) {
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap();
+            let fonts = Arc::new(FakeFonts::new(baseline));
+            let zoom = zoom(&fonts, baseline);
+            let profile = profile(zoom_config);
+            let pid = |id: u64| PID + id as i32;
+
+            runtime.block_on(async {
+                for &(id, focused) in &focus {
+                    let window = window(id, pid(id));
+                    if focused {
+                        zoom.on_focus(&window, pid(id), &profile).await.unwrap();
+                    } else {
+                        zoom.on_blur(&window, pid(id), Some(&profile)).await.unwrap();
+                    }
+                }
+                for id in 0..3 {
+                    zoom.on_blur(&window(id, pid(id)), pid(id), Some(&profile))
+                        .await
+                        .unwrap();
+                }
+            });
+
+            for id in 0..3 {
+                prop_assert!((fonts.size(pid(id)) - baseline).abs() < 1e-9);
+                let state = zoom.window_states.get(&pid(id)).unwrap().clone();
+                prop_assert!((state.current_zoom_factor - 1.0).abs() < 1e-9);
+            }
+        }
+    }
+}
@@ -0,0 +1,62 @@
+//! Track niri window focus and zoom kitty terminals to match
+//!
+//! The `kitty-focus-tracker` binary is one consumer of this crate; a session
+//! daemon can compose the same pieces into its own pipeline:
+//!
+//! - `niri`: [`NiriRegistry`] turns niri's IPC event stream into [`NiriEvent`]s,
+//!   and [`Handlers`] run [`EventHandler`]s concurrently off that stream
+//! - `kitty`: [`KittyRegistry`] talks to kitty over remote control and
+//!   [`KittyResizer`] runs focus effects (zoom, dim, colors, spacing) on a
+//!   stream of niri events, picking a profile for each window with [`Rules`]
+//! - `cli` (default): the binary's subcommands, config file loading and logging
+//!
+//! Each feature enables the ones before it. See `examples/` for pipelines.
+
+pub mod metrics;
+
+#[cfg(feature = "niri")]
+pub mod handlers;
+#[cfg(feature = "niri")]
+pub mod niri;
+
+#[cfg(feature = "kitty")]
+pub mod config;
+#[cfg(feature = "kitty")]
+pub mod control;
+#[cfg(feature = "kitty")]
+pub mod effects;
+#[cfg(feature = "kitty")]
+pub mod kitty;
+#[cfg(feature = "kitty")]
+pub mod reload;
+#[cfg(feature = "kitty")]
+pub mod rules;
+
+#[cfg(feature = "cli")]
+pub mod commands;
+#[cfg(feature = "cli")]
+pub mod logging;
+
+#[cfg(feature = "niri")]
+pub use handlers::{EventHandler, EventKind, HandlerRegistry, HandlerResult, Handlers};
+#[cfg(feature = "niri")]
+pub use niri::registry::NiriRegistry;
+#[cfg(feature = "niri")]
+pub use niri::types::{NiriEvent, OutputInfo, WindowInfo};
+
+#[cfg(feature = "kitty")]
+pub use config::{Config, RegistryConfig, ZoomConfig};
+#[cfg(feature = "kitty")]
+pub use effects::{EffectResult, FocusEffect};
+#[cfg(feature = "kitty")]
+pub use kitty::appearance::AppearanceController;
+#[cfg(feature = "kitty")]
+pub use kitty::font::FontController;
+#[cfg(feature = "kitty")]
+pub use kitty::resizer::{ResizerHandle, WindowStatus};
+#[cfg(feature = "kitty")]
+pub use kitty::zoom::FontZoom;
+#[cfg(feature = "kitty")]
+pub use kitty::{KittyRegistry, KittyResizer, ZoomingResult};
+#[cfg(feature = "kitty")]
+pub use rules::{Rule, Rules, ZoomProfile};
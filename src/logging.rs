@@ -0,0 +1,58 @@
+use crate::config::Verbosity;
+use tracing_subscriber::EnvFilter;
+use tracing_subscriber::prelude::*;
+
+const CRATE_TARGET: &str = "kitty_focus_tracker";
+
+/// Build the log filter: `RUST_LOG` wins, otherwise the CLI/config verbosity
+/// applies to this crate and dependencies stay at warn
+fn build_filter(verbosity: Verbosity) -> EnvFilter {
+    EnvFilter::try_from_default_env().unwrap_or_else(|_| {
+        EnvFilter::new(format!(
+            "warn,{}={}",
+            CRATE_TARGET,
+            verbosity.level_filter()
+        ))
+    })
+}
+
+/// Syslog identifier used for journald output, matching the unit name used by run.sh
+fn syslog_identifier() -> String {
+    std::env::var("ZOOMING_APPNAME").unwrap_or_else(|_| "kitty-focus-tracker".to_string())
+}
+
+/// Install the global tracing subscriber
+///
+/// With `journald` set, events are sent natively to journald with their span and
+/// event fields as unprefixed journal fields (`WINDOW_ID`, `PID`, `KITTY_PID`), so
+/// `journalctl WINDOW_ID=42` works. Falls back to stderr if journald is unreachable.
+pub fn init(verbosity: Verbosity, journald: bool) {
+    let filter = build_filter(verbosity);
+
+    let journald_error = if journald {
+        match tracing_journald::layer() {
+            Ok(layer) => {
+                let layer = layer
+                    .with_field_prefix(None)
+                    .with_syslog_identifier(syslog_identifier());
+                tracing_subscriber::registry()
+                    .with(filter)
+                    .with(layer)
+                    .init();
+                return;
+            }
+            Err(e) => Some(e),
+        }
+    } else {
+        None
+    };
+
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(tracing_subscriber::fmt::layer().with_writer(std::io::stderr))
+        .init();
+
+    if let Some(e) = journald_error {
+        tracing::warn!(error = %e, "journald unavailable, logging to stderr");
+    }
+}
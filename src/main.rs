@@ -1,19 +1,21 @@
 use clap::{Parser, Subcommand};
-use commands::ConfSizeCommand;
-use commands::conf_size::handle_conf_size_command;
-use commands::FontCommand;
-use commands::fonts::handle_font_command;
-use commands::systemd::generate_systemd_service;
-use commands::zoomer::run_zoomer;
-use config::{CliArgs, CliZoomArgs, Config, Verbosity};
-use kitty::KittyRegistry;
-use kitty::resizer::KittyResizer;
-use niri::registry::NiriRegistry;
-
-mod commands;
-mod config;
-mod kitty;
-mod niri;
+use kitty_focus_tracker::commands::ConfSizeCommand;
+use kitty_focus_tracker::commands::ConfigCommand;
+use kitty_focus_tracker::commands::DoctorCommand;
+use kitty_focus_tracker::commands::FontCommand;
+use kitty_focus_tracker::commands::SetupKittyCommand;
+use kitty_focus_tracker::commands::StatusCommand;
+use kitty_focus_tracker::commands::conf_size::handle_conf_size_command;
+use kitty_focus_tracker::commands::config::handle_config_command;
+use kitty_focus_tracker::commands::doctor::handle_doctor_command;
+use kitty_focus_tracker::commands::fonts::handle_font_command;
+use kitty_focus_tracker::commands::setup_kitty::handle_setup_kitty_command;
+use kitty_focus_tracker::commands::status::handle_status_command;
+use kitty_focus_tracker::commands::systemd::generate_systemd_service;
+use kitty_focus_tracker::commands::zoomer::run_zoomer;
+use kitty_focus_tracker::config::{CliArgs, CliZoomArgs, Config, Verbosity};
+use kitty_focus_tracker::kitty::KittyRegistry;
+use kitty_focus_tracker::logging;
 
 #[derive(Subcommand, Debug)]
 enum CliSubcommand {
@@ -43,12 +45,11 @@ enum CliSubcommand {
         #[arg(
             short,
             long,
-            default_value = "kitty",
-            help = "Application ID to track (e.g., 'kitty')"
+            help = "Application ID to track (e.g., 'kitty'; default: app_id from config, else kitty)"
         )]
-        app_id: String,
+        app_id: Option<String>,
 
-        #[arg(short, long = "verbose", action = clap::ArgAction::Count, help = "Increase verbosity level (use -v, -vv, -vvv, -vvvv)")]
+        #[arg(short, long = "verbose", action = clap::ArgAction::Count, help = "Increase verbosity level (-v debug, -vv trace; RUST_LOG overrides)")]
         verbose_count: u8,
 
         #[arg(long, value_name = "SIZE", help = "Absolute font size to set on focus")]
@@ -66,6 +67,24 @@ enum CliSubcommand {
     #[command(subcommand)]
     #[command(about = "Manually control kitty font sizes")]
     Font(FontCommand),
+    #[command(
+        name = "status",
+        about = "Show the running daemon's kitty instances and zoom state"
+    )]
+    Status(StatusCommand),
+    #[command(
+        name = "doctor",
+        about = "Check niri, kitty and config prerequisites and suggest fixes"
+    )]
+    Doctor(DoctorCommand),
+    #[command(
+        name = "setup-kitty",
+        about = "Configure kitty remote control (password, listen_on) for the tracker"
+    )]
+    SetupKitty(SetupKittyCommand),
+    #[command(subcommand)]
+    #[command(about = "Create, inspect and validate config.toml")]
+    Config(ConfigCommand),
 }
 
 #[derive(Parser, Debug)]
@@ -74,49 +93,53 @@ struct Args {
     #[arg(
         short,
         long,
-        default_value = "kitty",
-        long,
-        help = "Application ID to track when running in default mode"
+        help = "Application ID to track when running in default mode (default: kitty)"
     )]
-    app_id: String,
+    app_id: Option<String>,
 
-    #[arg(short, long = "verbose", action = clap::ArgAction::Count, help = "Increase verbosity level (use -v, -vv, -vvv, -vvvv)")]
+    #[arg(short, long = "verbose", action = clap::ArgAction::Count, help = "Increase verbosity level (-v debug, -vv trace; RUST_LOG overrides)")]
     verbose_count: u8,
 
-    #[arg(long, default_value = "5", long, help = "Socket timeout in seconds")]
-    socket_timeout: u64,
-
     #[arg(
         long,
-        default_value = "3",
-        long,
-        help = "Maximum connection retry attempts"
+        help = "Log to journald with structured fields instead of stderr"
     )]
-    max_retries: u32,
+    journald: bool,
 
     #[arg(
         long,
-        default_value = "30",
-        long,
-        help = "Maximum number of concurrent connections"
+        value_name = "SECS",
+        help = "Socket timeout in seconds (default: 5)"
     )]
-    max_connections: usize,
+    socket_timeout: Option<u64>,
 
     #[arg(
         long,
-        default_value = "1800",
+        value_name = "N",
+        help = "Maximum connection retry attempts (default: 3)"
+    )]
+    max_retries: Option<u32>,
+
+    #[arg(
         long,
-        help = "Idle connection timeout in seconds"
+        value_name = "N",
+        help = "Maximum number of concurrent connections (default: 30)"
     )]
-    idle_timeout: u64,
+    max_connections: Option<usize>,
 
     #[arg(
         long,
-        default_value = "300",
+        value_name = "SECS",
+        help = "Idle connection timeout in seconds (default: 1800)"
+    )]
+    idle_timeout: Option<u64>,
+
+    #[arg(
         long,
-        help = "Connection pool reaping interval in seconds"
+        value_name = "SECS",
+        help = "Connection pool reaping interval in seconds (default: 300)"
     )]
-    reap_interval: u64,
+    reap_interval: Option<u64>,
 
     #[arg(long, value_name = "SIZE", help = "Absolute font size to set on focus")]
     zoom_absolute: Option<f64>,
@@ -127,19 +150,24 @@ struct Args {
     #[arg(long, value_name = "FACTOR", help = "Multiplicative zoom factor (e.g., 1.5 for *1.5 on focus, /1.5 on blur)")]
     zoom_multiplicative: Option<f64>,
 
-    #[arg(long, value_name = "N", default_value = "1", help = "Step size for zoom operations (default: 1)")]
-    zoom_step_size: f64,
+    #[arg(
+        long,
+        value_name = "N",
+        help = "Step size for zoom operations (default: 1)"
+    )]
+    zoom_step_size: Option<f64>,
 
     #[command(subcommand)]
     command: Option<CliSubcommand>,
 }
 
- impl Args {
+impl Args {
     /// Convert to CliArgs for config loading
     fn to_cli_args(&self) -> CliArgs {
         CliArgs {
             app_id: self.app_id.clone(),
             verbosity: Verbosity::from_count(self.verbose_count),
+            journald: self.journald,
             socket_timeout: self.socket_timeout,
             max_retries: self.max_retries,
             max_connections: self.max_connections,
@@ -154,7 +182,7 @@ struct Args {
             absolute: self.zoom_absolute,
             additive: self.zoom_additive,
             multiplicative: self.zoom_multiplicative,
-            step_size: Some(self.zoom_step_size.into()),
+            step_size: self.zoom_step_size,
         }
     }
 }
@@ -176,7 +204,13 @@ async fn main() -> std::io::Result<()> {
     }
 
     if let Some(CliSubcommand::Cleanup) = args.command {
-        let config = Config::load(None, None).unwrap_or_default();
+        logging::init(cli_args.verbosity, false);
+        let config = Config::load(None, None).map_err(|e| {
+            std::io::Error::other(format!(
+                "Invalid config: {}. Run `kitty-focus-tracker config check` for details",
+                e
+            ))
+        })?;
         let registry = KittyRegistry::new(config.to_registry_config());
         registry.cleanup_dead_connections().await;
         eprintln!("Cleanup complete");
@@ -198,49 +232,75 @@ async fn main() -> std::io::Result<()> {
             multiplicative: zoom_multiplicative,
             step_size: zoom_step_size,
         };
-        let config = Config::load(Some(&cli_args), Some(&zoom_args)).unwrap_or_default();
-        let verbosity = Verbosity::from_count(args.verbose_count + verbose_count);
-        run_zoomer(zoomer_app_id, verbosity, config)
+        let cli_args = CliArgs {
+            app_id: zoomer_app_id.or(cli_args.app_id),
+            verbosity: Verbosity::from_count(args.verbose_count + verbose_count),
+            ..cli_args
+        };
+        let config = Config::load(Some(&cli_args), Some(&zoom_args)).map_err(|e| {
+            std::io::Error::other(format!(
+                "Invalid config: {}. Run `kitty-focus-tracker config check` for details",
+                e
+            ))
+        })?;
+        logging::init(config.effective_verbosity(), config.journald);
+        let app_id = config.app_id.clone();
+        let load = move || Config::load(Some(&cli_args), Some(&zoom_args));
+        run_zoomer(app_id, config, load)
             .await
-            .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e.to_string()))?;
+            .map_err(|e| std::io::Error::other(e.to_string()))?;
         return Ok(());
     }
 
     if let Some(CliSubcommand::Font(font_cmd)) = args.command {
         handle_font_command(font_cmd)
             .await
-            .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e.to_string()))?;
+            .map_err(|e| std::io::Error::other(e.to_string()))?;
         return Ok(());
     }
 
-    // Load config from file, env, and CLI args
-    let zoom_args = args.to_zoom_args();
-    let config = Config::load(Some(&cli_args), Some(&zoom_args)).unwrap_or_else(|e| {
-        eprintln!("Config error: {}, using defaults", e);
-        Config::default()
-    });
+    if let Some(CliSubcommand::Status(cmd)) = args.command {
+        handle_status_command(cmd)
+            .await
+            .map_err(|e| std::io::Error::other(e.to_string()))?;
+        return Ok(());
+    }
 
-    let app_id = config.app_id.clone();
-    let verbosity = config.verbosity;
+    if let Some(CliSubcommand::Doctor(cmd)) = args.command {
+        handle_doctor_command(cmd)
+            .await
+            .map_err(|e| std::io::Error::other(e.to_string()))?;
+        return Ok(());
+    }
 
-    if verbosity.log_window_events() {
-        eprintln!("Starting event stream for window focus changes...");
-        eprintln!("Tracking app_id: {}", app_id);
+    if let Some(CliSubcommand::SetupKitty(cmd)) = args.command {
+        handle_setup_kitty_command(cmd).map_err(|e| std::io::Error::other(e.to_string()))?;
+        return Ok(());
     }
 
-    let kitty_registry = KittyRegistry::new(config.to_registry_config());
-    kitty_registry.start_reaper().await;
+    let zoom_args = args.to_zoom_args();
 
-    let niri_registry = NiriRegistry::new_with_verbosity(verbosity)
-        .await
-        .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e.to_string()))?;
+    if let Some(CliSubcommand::Config(cmd)) = args.command {
+        handle_config_command(cmd, &cli_args, &zoom_args)
+            .map_err(|e| std::io::Error::other(e.to_string()))?;
+        return Ok(());
+    }
 
-    let mut kitty_resizer = KittyResizer::with_zoom_config(kitty_registry, config.zoom);
+    // Load config from file, env, and CLI args
+    let config = Config::load(Some(&cli_args), Some(&zoom_args)).map_err(|e| {
+        std::io::Error::other(format!(
+            "Invalid config: {}. Run `kitty-focus-tracker config check` for details",
+            e
+        ))
+    })?;
 
-    let kitty_events =
-        niri_registry.windows_matching(|window| window.app_id.as_deref() == Some(&app_id));
+    logging::init(config.effective_verbosity(), config.journald);
 
-    let _ = kitty_resizer.process_events(kitty_events).await;
+    let app_id = config.app_id.clone();
+    let load = move || Config::load(Some(&cli_args), Some(&zoom_args));
+    run_zoomer(app_id, config, load)
+        .await
+        .map_err(|e| std::io::Error::other(e.to_string()))?;
 
     Ok(())
 }
@@ -0,0 +1,482 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tracing::{debug, info, warn};
+
+const PREFIX: &str = "kitty_focus_tracker";
+
+/// Latency buckets in seconds, from a single socket round-trip up to a slow multi-step zoom
+const LATENCY_BUCKETS: &[f64] = &[
+    0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0,
+];
+
+static METRICS: OnceLock<Metrics> = OnceLock::new();
+
+/// Process-wide metrics, created on first use
+pub fn metrics() -> &'static Metrics {
+    METRICS.get_or_init(Metrics::new)
+}
+
+pub struct Counter(AtomicU64);
+
+impl Counter {
+    fn new() -> Self {
+        Self(AtomicU64::new(0))
+    }
+
+    pub fn inc(&self) {
+        self.0.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Counter partitioned by a fixed set of label names
+pub struct CounterVec {
+    labels: &'static [&'static str],
+    values: Mutex<BTreeMap<Vec<String>, u64>>,
+}
+
+impl CounterVec {
+    fn new(labels: &'static [&'static str]) -> Self {
+        Self {
+            labels,
+            values: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    pub fn inc(&self, label_values: &[&str]) {
+        debug_assert_eq!(label_values.len(), self.labels.len());
+        let key = label_values.iter().map(|v| v.to_string()).collect();
+        *self.values.lock().unwrap().entry(key).or_insert(0) += 1;
+    }
+
+    pub fn get(&self, label_values: &[&str]) -> u64 {
+        let key: Vec<String> = label_values.iter().map(|v| v.to_string()).collect();
+        self.values.lock().unwrap().get(&key).copied().unwrap_or(0)
+    }
+}
+
+pub struct Gauge(AtomicI64);
+
+impl Gauge {
+    fn new() -> Self {
+        Self(AtomicI64::new(0))
+    }
+
+    pub fn set(&self, value: i64) {
+        self.0.store(value, Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> i64 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+pub struct Histogram {
+    bounds: &'static [f64],
+    buckets: Vec<AtomicU64>,
+    sum_micros: AtomicU64,
+    count: AtomicU64,
+}
+
+impl Histogram {
+    fn new(bounds: &'static [f64]) -> Self {
+        Self {
+            bounds,
+            buckets: bounds.iter().map(|_| AtomicU64::new(0)).collect(),
+            sum_micros: AtomicU64::new(0),
+            count: AtomicU64::new(0),
+        }
+    }
+
+    pub fn observe(&self, duration: Duration) {
+        let secs = duration.as_secs_f64();
+        for (bound, bucket) in self.bounds.iter().zip(&self.buckets) {
+            if secs <= *bound {
+                bucket.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        self.sum_micros
+            .fetch_add(duration.as_micros() as u64, Ordering::Relaxed);
+        self.count.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count.load(Ordering::Relaxed)
+    }
+}
+
+pub struct Metrics {
+    /// Niri events handed to consumers, by kind
+    pub niri_events: CounterVec,
+    /// Layout actions sent to niri, by outcome
+    pub niri_actions: CounterVec,
+    /// Events an event handler failed or panicked on, by handler and outcome
+    pub handler_failures: CounterVec,
+    /// Focus effect calls that failed, by effect
+    pub effect_failures: CounterVec,
+    /// Individual remote control messages sent to kitty, by op and outcome
+    pub kitty_commands: CounterVec,
+    /// Font operations by final `ZoomingResult`
+    pub zoom_results: CounterVec,
+    /// Retry attempts after a failed connection or command
+    pub kitty_retries: Counter,
+    /// Connection status changes per kitty instance
+    pub status_transitions: CounterVec,
+    /// Open pooled kitty connections
+    pub pool_connections: Gauge,
+    /// Time from niri reporting focus to the resizer finishing the zoom
+    pub focus_to_zoom: Histogram,
+    pending_focus: Mutex<Option<(u64, Instant)>>,
+}
+
+impl Metrics {
+    fn new() -> Self {
+        Self {
+            niri_events: CounterVec::new(&["kind"]),
+            niri_actions: CounterVec::new(&["outcome"]),
+            handler_failures: CounterVec::new(&["handler", "outcome"]),
+            effect_failures: CounterVec::new(&["effect"]),
+            kitty_commands: CounterVec::new(&["op", "outcome"]),
+            zoom_results: CounterVec::new(&["result"]),
+            kitty_retries: Counter::new(),
+            status_transitions: CounterVec::new(&["from", "to"]),
+            pool_connections: Gauge::new(),
+            focus_to_zoom: Histogram::new(LATENCY_BUCKETS),
+            pending_focus: Mutex::new(None),
+        }
+    }
+
+    /// Mark the moment niri reported focus on a window
+    pub fn focus_received(&self, window_id: u64) {
+        *self.pending_focus.lock().unwrap() = Some((window_id, Instant::now()));
+    }
+
+    /// Record focus-to-zoom latency if this window is the most recently focused one
+    pub fn focus_applied(&self, window_id: u64) {
+        let mut pending = self.pending_focus.lock().unwrap();
+        if let Some((id, received_at)) = *pending
+            && id == window_id
+        {
+            self.focus_to_zoom.observe(received_at.elapsed());
+            *pending = None;
+        }
+    }
+
+    /// Render all metrics in the Prometheus text exposition format
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        render_counter_vec(
+            &mut out,
+            "niri_events_total",
+            "Niri window events delivered to consumers",
+            &self.niri_events,
+        );
+        render_counter_vec(
+            &mut out,
+            "niri_actions_total",
+            "Layout actions sent to niri",
+            &self.niri_actions,
+        );
+        render_counter_vec(
+            &mut out,
+            "handler_failures_total",
+            "Events an event handler failed or panicked on",
+            &self.handler_failures,
+        );
+        render_counter_vec(
+            &mut out,
+            "effect_failures_total",
+            "Focus effect calls that failed",
+            &self.effect_failures,
+        );
+        render_counter_vec(
+            &mut out,
+            "kitty_commands_total",
+            "Remote control messages sent to kitty",
+            &self.kitty_commands,
+        );
+        render_counter_vec(
+            &mut out,
+            "zoom_results_total",
+            "Font operations by result",
+            &self.zoom_results,
+        );
+        render_counter(
+            &mut out,
+            "kitty_retries_total",
+            "Retried kitty font operations",
+            &self.kitty_retries,
+        );
+        render_counter_vec(
+            &mut out,
+            "kitty_status_transitions_total",
+            "Kitty connection status transitions",
+            &self.status_transitions,
+        );
+        render_gauge(
+            &mut out,
+            "kitty_pool_connections",
+            "Open pooled kitty connections",
+            &self.pool_connections,
+        );
+        render_histogram(
+            &mut out,
+            "focus_to_zoom_seconds",
+            "Latency from niri focus event to completed zoom",
+            &self.focus_to_zoom,
+        );
+        out
+    }
+}
+
+fn render_header(out: &mut String, name: &str, help: &str, kind: &str) {
+    let _ = writeln!(out, "# HELP {}_{} {}", PREFIX, name, help);
+    let _ = writeln!(out, "# TYPE {}_{} {}", PREFIX, name, kind);
+}
+
+fn render_counter(out: &mut String, name: &str, help: &str, counter: &Counter) {
+    render_header(out, name, help, "counter");
+    let _ = writeln!(out, "{}_{} {}", PREFIX, name, counter.get());
+}
+
+fn render_counter_vec(out: &mut String, name: &str, help: &str, counter: &CounterVec) {
+    render_header(out, name, help, "counter");
+    for (values, count) in counter.values.lock().unwrap().iter() {
+        let labels = counter
+            .labels
+            .iter()
+            .zip(values)
+            .map(|(label, value)| format!("{}=\"{}\"", label, escape_label(value)))
+            .collect::<Vec<_>>()
+            .join(",");
+        let _ = writeln!(out, "{}_{}{{{}}} {}", PREFIX, name, labels, count);
+    }
+}
+
+fn render_gauge(out: &mut String, name: &str, help: &str, gauge: &Gauge) {
+    render_header(out, name, help, "gauge");
+    let _ = writeln!(out, "{}_{} {}", PREFIX, name, gauge.get());
+}
+
+fn render_histogram(out: &mut String, name: &str, help: &str, histogram: &Histogram) {
+    render_header(out, name, help, "histogram");
+    for (bound, bucket) in histogram.bounds.iter().zip(&histogram.buckets) {
+        let _ = writeln!(
+            out,
+            "{}_{}_bucket{{le=\"{}\"}} {}",
+            PREFIX,
+            name,
+            bound,
+            bucket.load(Ordering::Relaxed)
+        );
+    }
+    let count = histogram.count();
+    let sum = histogram.sum_micros.load(Ordering::Relaxed) as f64 / 1_000_000.0;
+    let _ = writeln!(out, "{}_{}_bucket{{le=\"+Inf\"}} {}", PREFIX, name, count);
+    let _ = writeln!(out, "{}_{}_sum {}", PREFIX, name, sum);
+    let _ = writeln!(out, "{}_{}_count {}", PREFIX, name, count);
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn default_textfile_interval() -> u64 {
+    15
+}
+
+/// Metrics exporter configuration; both exporters are off unless configured
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct MetricsConfig {
+    /// Address for the Prometheus text endpoint, e.g. "127.0.0.1:9464" (loopback only)
+    pub listen: Option<String>,
+
+    /// File to periodically write the Prometheus text exposition to
+    pub textfile: Option<PathBuf>,
+
+    /// Interval between text-file dumps in seconds
+    #[serde(default = "default_textfile_interval")]
+    pub textfile_interval_secs: u64,
+}
+
+impl Default for MetricsConfig {
+    fn default() -> Self {
+        Self {
+            listen: None,
+            textfile: None,
+            textfile_interval_secs: default_textfile_interval(),
+        }
+    }
+}
+
+impl MetricsConfig {
+    /// Parse and check the listen address; only loopback addresses are accepted
+    pub fn listen_addr(&self) -> Result<Option<SocketAddr>, String> {
+        let Some(listen) = &self.listen else {
+            return Ok(None);
+        };
+
+        let addr: SocketAddr = listen
+            .parse()
+            .map_err(|e| format!("Invalid metrics listen address '{}': {}", listen, e))?;
+
+        if !addr.ip().is_loopback() {
+            return Err(format!(
+                "Metrics listen address {} is not a loopback address",
+                addr
+            ));
+        }
+
+        Ok(Some(addr))
+    }
+}
+
+/// Start whichever exporters are configured
+pub async fn start_exporters(config: &MetricsConfig) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(addr) = config.listen_addr()? {
+        let listener = TcpListener::bind(addr).await?;
+        info!(%addr, "Serving Prometheus metrics");
+        tokio::spawn(serve(listener));
+    }
+
+    if let Some(path) = &config.textfile {
+        let interval = Duration::from_secs(config.textfile_interval_secs.max(1));
+        info!(path = %path.display(), ?interval, "Writing metrics text file");
+        tokio::spawn(write_textfile_periodically(path.clone(), interval));
+    }
+
+    Ok(())
+}
+
+async fn serve(listener: TcpListener) {
+    loop {
+        let (mut stream, peer) = match listener.accept().await {
+            Ok(conn) => conn,
+            Err(e) => {
+                warn!(error = %e, "Metrics listener accept failed");
+                continue;
+            }
+        };
+
+        tokio::spawn(async move {
+            let mut buf = [0u8; 1024];
+            let n = match stream.read(&mut buf).await {
+                Ok(n) => n,
+                Err(e) => {
+                    debug!(%peer, error = %e, "Failed to read metrics request");
+                    return;
+                }
+            };
+
+            let request = String::from_utf8_lossy(&buf[..n]);
+            let response = if request.starts_with("GET /metrics ") || request.starts_with("GET / ")
+            {
+                let body = metrics().render();
+                format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                )
+            } else {
+                "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                    .to_string()
+            };
+
+            if let Err(e) = stream.write_all(response.as_bytes()).await {
+                debug!(%peer, error = %e, "Failed to write metrics response");
+            }
+        });
+    }
+}
+
+async fn write_textfile_periodically(path: PathBuf, interval: Duration) {
+    loop {
+        tokio::time::sleep(interval).await;
+        if let Err(e) = write_textfile(&path) {
+            warn!(path = %path.display(), error = %e, "Failed to write metrics text file");
+        }
+    }
+}
+
+/// Write via a temporary file and rename so readers never see a partial dump
+fn write_textfile(path: &PathBuf) -> std::io::Result<()> {
+    let tmp_path = path.with_extension("prom.tmp");
+    std::fs::write(&tmp_path, metrics().render())?;
+    std::fs::rename(&tmp_path, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_counter_vec_with_labels() {
+        let metrics = Metrics::new();
+        metrics.kitty_commands.inc(&["+", "ok"]);
+        metrics.kitty_commands.inc(&["+", "ok"]);
+        metrics.kitty_commands.inc(&["*", "error"]);
+
+        let text = metrics.render();
+        assert!(
+            text.contains("kitty_focus_tracker_kitty_commands_total{op=\"+\",outcome=\"ok\"} 2")
+        );
+        assert!(
+            text.contains("kitty_focus_tracker_kitty_commands_total{op=\"*\",outcome=\"error\"} 1")
+        );
+    }
+
+    #[test]
+    fn test_histogram_buckets_are_cumulative() {
+        let metrics = Metrics::new();
+        metrics.focus_to_zoom.observe(Duration::from_millis(3));
+        metrics.focus_to_zoom.observe(Duration::from_millis(200));
+
+        let text = metrics.render();
+        assert!(text.contains("kitty_focus_tracker_focus_to_zoom_seconds_bucket{le=\"0.001\"} 0"));
+        assert!(text.contains("kitty_focus_tracker_focus_to_zoom_seconds_bucket{le=\"0.005\"} 1"));
+        assert!(text.contains("kitty_focus_tracker_focus_to_zoom_seconds_bucket{le=\"0.25\"} 2"));
+        assert!(text.contains("kitty_focus_tracker_focus_to_zoom_seconds_bucket{le=\"+Inf\"} 2"));
+        assert!(text.contains("kitty_focus_tracker_focus_to_zoom_seconds_count 2"));
+    }
+
+    #[test]
+    fn test_focus_latency_only_for_latest_window() {
+        let metrics = Metrics::new();
+        metrics.focus_received(1);
+        metrics.focus_received(2);
+        metrics.focus_applied(1);
+        assert_eq!(metrics.focus_to_zoom.count(), 0);
+        metrics.focus_applied(2);
+        assert_eq!(metrics.focus_to_zoom.count(), 1);
+        metrics.focus_applied(2);
+        assert_eq!(metrics.focus_to_zoom.count(), 1);
+    }
+
+    #[test]
+    fn test_listen_addr_requires_loopback() {
+        let mut config = MetricsConfig {
+            listen: Some("127.0.0.1:9464".to_string()),
+            ..Default::default()
+        };
+        assert!(config.listen_addr().unwrap().is_some());
+
+        config.listen = Some("0.0.0.0:9464".to_string());
+        assert!(config.listen_addr().is_err());
+    }
+}
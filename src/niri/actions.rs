@@ -0,0 +1,54 @@
+use niri_ipc::socket::Socket;
+use niri_ipc::{Action, Request, Response, SizeChange};
+use std::io;
+use tracing::debug;
+
+use crate::metrics::metrics;
+
+/// Sends layout actions to niri, each on its own socket connection
+///
+/// The event stream socket in `NiriRegistry` is read-only once streaming, so
+/// actions need a connection of their own.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NiriActions;
+
+impl NiriActions {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Perform `action` and wait for niri to acknowledge it
+    ///
+    /// The socket round-trip blocks, so it runs on tokio's blocking pool.
+    pub async fn send(&self, action: Action) -> io::Result<()> {
+        debug!(?action, "Sending niri action");
+        let result = tokio::task::spawn_blocking(move || Self::request(action))
+            .await
+            .unwrap_or_else(|e| Err(io::Error::other(e)));
+        metrics()
+            .niri_actions
+            .inc(&[if result.is_ok() { "ok" } else { "error" }]);
+        result
+    }
+
+    /// Set the width of the column holding `window_id`, in logical pixels
+    pub async fn set_window_width(&self, window_id: u64, width: i32) -> io::Result<()> {
+        self.send(Action::SetWindowWidth {
+            id: Some(window_id),
+            change: SizeChange::SetFixed(width),
+        })
+        .await
+    }
+
+    fn request(action: Action) -> io::Result<()> {
+        let mut socket = Socket::connect()?;
+        match socket.send(Request::Action(action))? {
+            Ok(Response::Handled) => Ok(()),
+            Ok(other) => Err(io::Error::other(format!(
+                "unexpected niri reply: {:?}",
+                other
+            ))),
+            Err(message) => Err(io::Error::other(message)),
+        }
+    }
+}
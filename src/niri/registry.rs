@@ -1,22 +1,21 @@
-use crate::config::Verbosity;
 use niri_ipc::socket::Socket;
-use niri_ipc::{Event, Request, Response};
+use niri_ipc::{Event, Request, Response, Window, Workspace};
+use std::collections::HashMap;
 use tokio::sync::mpsc;
 use tokio_stream::{Stream, StreamExt, wrappers::UnboundedReceiverStream};
+use tracing::{debug, trace, warn};
 
-use crate::niri::types::{NiriEvent, WindowInfo};
+use crate::metrics::metrics;
+use crate::niri::types::{NiriEvent, OutputInfo, WindowInfo};
 
 pub struct NiriRegistry {
     socket: Option<Socket>,
     event_tx: mpsc::UnboundedSender<NiriEvent>,
     event_rx: Option<mpsc::UnboundedReceiver<NiriEvent>>,
-    verbosity: Verbosity,
 }
 
 impl NiriRegistry {
-    pub async fn new_with_verbosity(
-        verbosity: Verbosity,
-    ) -> Result<Self, Box<dyn std::error::Error>> {
+    pub async fn new() -> Result<Self, Box<dyn std::error::Error>> {
         let mut socket = Socket::connect()?;
         let reply = socket.send(Request::EventStream)?;
 
@@ -32,7 +31,6 @@ impl NiriRegistry {
             socket: Some(socket),
             event_tx,
             event_rx: Some(event_rx),
-            verbosity,
         };
 
         registry.start_event_listener().await;
@@ -54,17 +52,14 @@ impl NiriRegistry {
             .filter(|event| matches!(event, NiriEvent::Blur { .. }))
     }
 
+    /// Events for windows matching `predicate`, plus those about no window in
+    /// particular, such as output changes
     pub fn windows_matching<P>(self, predicate: P) -> impl Stream<Item = NiriEvent> + Send + Unpin
     where
         P: Fn(&WindowInfo) -> bool + Send + Sync,
     {
-        self.into_events().filter(move |event| {
-            if let Some(window) = event.window() {
-                (predicate)(window)
-            } else {
-                false
-            }
-        })
+        self.into_events()
+            .filter(move |event| event.window().is_none_or(&predicate))
     }
 
     pub fn window_events(self) -> impl Stream<Item = NiriEvent> + Send + Unpin {
@@ -83,56 +78,59 @@ impl NiriRegistry {
         let socket = self.socket.take().unwrap();
         let mut read_event = socket.read_events();
         let tx = self.event_tx.clone();
-        let verbosity = self.verbosity;
 
         tokio::spawn(async move {
             let mut last_focused_window_id: Option<u64> = None;
+            // niri sends the full list on connect and again whenever it changes
+            let mut workspaces: Vec<Workspace> = Vec::new();
+            let mut outputs: Vec<OutputInfo> = Vec::new();
+            // Workspace and output of every window, to notice moves between outputs
+            let mut window_outputs: HashMap<u64, (Option<u64>, Option<String>)> = HashMap::new();
+            // Size of the focused window, to report resizes
+            let mut focused_size: Option<(i32, i32)> = None;
 
             while let Ok(event) = read_event() {
-                if verbosity.log_all_events() {
-                    eprintln!("Niri event: {:?}", event);
-                }
+                trace!(?event, "Niri event");
 
                 match event {
                     Event::WindowFocusTimestampChanged { id, .. } => {
-                        if verbosity.log_window_events() {
-                            eprintln!("WindowFocusTimestampChanged: id={}", id);
-                        }
+                        debug!(window_id = id, "WindowFocusTimestampChanged");
 
-                        if let Some(window_info) = Self::get_window_info(id).await {
-                            if verbosity.log_window_events() {
-                                eprintln!(
-                                    "Focus event: window_id={}, app_id={:?}, pid={:?}",
-                                    id, window_info.app_id, window_info.pid
-                                );
-                            }
+                        if let Some(window_info) = Self::get_window_info(id, &workspaces).await {
+                            debug!(
+                                window_id = id,
+                                app_id = ?window_info.app_id,
+                                pid = ?window_info.pid,
+                                "Focus event"
+                            );
 
-                            if let Some(prev_id) = last_focused_window_id {
-                                if prev_id != id {
-                                    if let Some(prev_window_info) = Self::get_window_info(prev_id).await {
-                                        if verbosity.log_window_events() {
-                                            eprintln!(
-                                                "Blur event: window_id={}, app_id={:?}, pid={:?}",
-                                                prev_id, prev_window_info.app_id, prev_window_info.pid
-                                            );
-                                        }
-                                        let niri_event = NiriEvent::Blur {
-                                            window_id: prev_id,
-                                            window: prev_window_info,
-                                        };
-                                        if let Err(_) = tx.send(niri_event) {
-                                            break;
-                                        }
-                                    }
+                            if let Some(prev_id) = last_focused_window_id
+                                && prev_id != id
+                                && let Some(prev_window_info) =
+                                    Self::get_window_info(prev_id, &workspaces).await
+                            {
+                                debug!(
+                                    window_id = prev_id,
+                                    app_id = ?prev_window_info.app_id,
+                                    pid = ?prev_window_info.pid,
+                                    "Blur event"
+                                );
+                                let niri_event = NiriEvent::Blur {
+                                    window_id: prev_id,
+                                    window: prev_window_info,
+                                };
+                                if !emit(&tx, niri_event) {
+                                    break;
                                 }
                             }
 
+                            focused_size = window_info.window_size;
                             let niri_event = NiriEvent::Focus {
                                 window_id: id,
                                 window: window_info,
                             };
 
-                            if let Err(_) = tx.send(niri_event) {
+                            if !emit(&tx, niri_event) {
                                 break;
                             }
 
@@ -143,46 +141,207 @@ impl NiriRegistry {
                         last_focused_window_id = None;
                     }
                     Event::WindowsChanged { windows } => {
-                        if last_focused_window_id.is_none() {
-                            if let Some(focused_window) = windows.iter().find(|w| w.is_focused) {
-                                if verbosity.log_window_events() {
-                                    eprintln!(
-                                        "Initial focus detected: window_id={}, app_id={:?}, pid={:?}",
-                                        focused_window.id, focused_window.app_id, focused_window.pid
-                                    );
-                                }
+                        window_outputs = windows
+                            .iter()
+                            .map(|w| {
+                                let output = output_of(w.workspace_id, &workspaces);
+                                (w.id, (w.workspace_id, output))
+                            })
+                            .collect();
 
-                                let window_info = WindowInfo::from_niri_window(focused_window);
-                                let niri_event = NiriEvent::Focus {
-                                    window_id: focused_window.id,
-                                    window: window_info,
-                                };
+                        if last_focused_window_id.is_none()
+                            && let Some(focused_window) = windows.iter().find(|w| w.is_focused)
+                        {
+                            debug!(
+                                window_id = focused_window.id,
+                                app_id = ?focused_window.app_id,
+                                pid = ?focused_window.pid,
+                                "Initial focus detected"
+                            );
 
-                                if let Err(_) = tx.send(niri_event) {
-                                    break;
-                                }
+                            let mut window_info = WindowInfo::from_niri_window(focused_window);
+                            window_info.resolve_workspace(&workspaces);
+                            focused_size = window_info.window_size;
+                            let niri_event = NiriEvent::Focus {
+                                window_id: focused_window.id,
+                                window: window_info,
+                            };
 
-                                last_focused_window_id = Some(focused_window.id);
+                            if !emit(&tx, niri_event) {
+                                break;
+                            }
+
+                            last_focused_window_id = Some(focused_window.id);
+                        }
+                    }
+                    Event::WindowOpenedOrChanged { window } => {
+                        let mut window_info = WindowInfo::from_niri_window(&window);
+                        window_info.resolve_workspace(&workspaces);
+
+                        let previous = window_outputs
+                            .insert(window.id, (window.workspace_id, window_info.output.clone()));
+                        if let Some((_, previous)) = previous
+                            && window_info.output.is_some()
+                            && previous != window_info.output
+                        {
+                            debug!(
+                                window_id = window.id,
+                                from = ?previous,
+                                to = ?window_info.output,
+                                "Window moved to another output"
+                            );
+                            let niri_event = NiriEvent::Moved {
+                                window_id: window.id,
+                                window: window_info,
+                            };
+                            if !emit(&tx, niri_event) {
+                                break;
                             }
                         }
                     }
+                    Event::WindowLayoutsChanged { changes } => {
+                        let Some(id) = last_focused_window_id else {
+                            continue;
+                        };
+                        let Some((_, layout)) = changes.iter().find(|(changed, _)| *changed == id)
+                        else {
+                            continue;
+                        };
+                        if focused_size == Some(layout.window_size) {
+                            continue;
+                        }
+                        focused_size = Some(layout.window_size);
+
+                        if let Some(window_info) = Self::get_window_info(id, &workspaces).await {
+                            debug!(window_id = id, size = ?layout.window_size, "Focused window resized");
+                            let niri_event = NiriEvent::Resized {
+                                window_id: id,
+                                window: window_info,
+                            };
+                            if !emit(&tx, niri_event) {
+                                break;
+                            }
+                        }
+                    }
+                    Event::WindowClosed { id } => {
+                        window_outputs.remove(&id);
+                        if !emit(&tx, NiriEvent::Destroy { window_id: id }) {
+                            break;
+                        }
+                    }
+                    Event::WorkspacesChanged {
+                        workspaces: changed,
+                    } => {
+                        trace!(count = changed.len(), "Workspace cache refreshed");
+                        workspaces = changed;
+
+                        // niri has no output event; hot-plugging shows up as workspaces
+                        // moving, so re-read the outputs on every workspace change
+                        if let Some(changed) = Self::get_outputs().await
+                            && changed != outputs
+                        {
+                            debug!(outputs = ?changed, "Outputs changed");
+                            outputs = changed;
+                            let niri_event = NiriEvent::OutputsChanged {
+                                outputs: outputs.clone(),
+                            };
+                            if !emit(&tx, niri_event) {
+                                break;
+                            }
+                        }
+
+                        let moved: Vec<u64> = window_outputs
+                            .iter_mut()
+                            .filter_map(|(&id, (workspace_id, output))| {
+                                let current = output_of(*workspace_id, &workspaces);
+                                (current.is_some() && current != *output).then(|| {
+                                    *output = current;
+                                    id
+                                })
+                            })
+                            .collect();
+                        if moved.is_empty() {
+                            continue;
+                        }
+
+                        let windows = Self::get_windows().await.unwrap_or_default();
+                        let mut receiver_gone = false;
+                        for window in windows.iter().filter(|w| moved.contains(&w.id)) {
+                            let mut window_info = WindowInfo::from_niri_window(window);
+                            window_info.resolve_workspace(&workspaces);
+                            debug!(
+                                window_id = window.id,
+                                output = ?window_info.output,
+                                "Window moved with its workspace"
+                            );
+                            let niri_event = NiriEvent::Moved {
+                                window_id: window.id,
+                                window: window_info,
+                            };
+                            if !emit(&tx, niri_event) {
+                                receiver_gone = true;
+                                break;
+                            }
+                        }
+                        if receiver_gone {
+                            break;
+                        }
+                    }
                     _ => continue,
                 }
             }
+
+            warn!("Niri event stream ended");
         });
     }
 
-    async fn get_window_info(window_id: u64) -> Option<WindowInfo> {
+    async fn get_windows() -> Option<Vec<Window>> {
+        let mut socket = Socket::connect().ok()?;
+        match socket.send(Request::Windows).ok()? {
+            Ok(Response::Windows(windows)) => Some(windows),
+            _ => None,
+        }
+    }
+
+    async fn get_outputs() -> Option<Vec<OutputInfo>> {
         let mut socket = Socket::connect().ok()?;
-        let reply = socket.send(Request::Windows).ok()?;
-        let windows = match reply {
-            Ok(Response::Windows(windows)) => windows,
+        let outputs = match socket.send(Request::Outputs).ok()? {
+            Ok(Response::Outputs(outputs)) => outputs,
             _ => return None,
         };
 
-        windows
+        let mut outputs: Vec<OutputInfo> =
+            outputs.values().map(OutputInfo::from_niri_output).collect();
+        outputs.sort_by(|a, b| a.name.cmp(&b.name));
+        Some(outputs)
+    }
+
+    async fn get_window_info(window_id: u64, workspaces: &[Workspace]) -> Option<WindowInfo> {
+        let windows = Self::get_windows().await?;
+
+        let mut window_info = windows
             .iter()
             .find(|w| w.id == window_id)
-            .map(WindowInfo::from_niri_window)
+            .map(WindowInfo::from_niri_window)?;
+        window_info.resolve_workspace(workspaces);
+
+        Some(window_info)
+    }
+}
+
+/// Output of the workspace with `workspace_id`, if known
+fn output_of(workspace_id: Option<u64>, workspaces: &[Workspace]) -> Option<String> {
+    workspaces
+        .iter()
+        .find(|w| Some(w.id) == workspace_id)
+        .and_then(|w| w.output.clone())
+}
+
+/// Send an event to consumers, counting it; returns false once the receiver is gone
+fn emit(tx: &mpsc::UnboundedSender<NiriEvent>, event: NiriEvent) -> bool {
+    metrics().niri_events.inc(&[event.kind()]);
+    if let NiriEvent::Focus { window_id, .. } = &event {
+        metrics().focus_received(*window_id);
     }
+    tx.send(event).is_ok()
 }
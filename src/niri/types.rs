@@ -1,11 +1,24 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Snapshot of a niri window; fields newer than id/app_id/pid/title default when absent
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct WindowInfo {
     pub id: u64,
     pub app_id: Option<String>,
     pub pid: Option<i32>,
     pub title: Option<String>,
+    pub workspace_id: Option<u64>,
+    /// Resolved from niri's workspace list; `None` for unnamed workspaces
+    pub workspace_name: Option<String>,
+    pub workspace_idx: Option<u8>,
+    pub output: Option<String>,
+    pub is_floating: bool,
+    pub is_urgent: bool,
+    /// Tile size in logical pixels, including borders
+    pub tile_size: Option<(f64, f64)>,
+    /// Window (client area) size in logical pixels
+    pub window_size: Option<(i32, i32)>,
 }
 
 impl WindowInfo {
@@ -15,8 +28,64 @@ impl WindowInfo {
             app_id: window.app_id.clone(),
             pid: window.pid,
             title: window.title.clone(),
+            workspace_id: window.workspace_id,
+            workspace_name: None,
+            workspace_idx: None,
+            output: None,
+            is_floating: window.is_floating,
+            is_urgent: window.is_urgent,
+            tile_size: Some(window.layout.tile_size),
+            window_size: Some(window.layout.window_size),
+        }
+    }
+
+    /// Fill in workspace name, index and output from niri's workspace list
+    pub fn resolve_workspace(&mut self, workspaces: &[niri_ipc::Workspace]) {
+        let Some(workspace) = workspaces.iter().find(|w| Some(w.id) == self.workspace_id) else {
+            return;
+        };
+
+        self.workspace_name = workspace.name.clone();
+        self.workspace_idx = Some(workspace.idx);
+        self.output = workspace.output.clone();
+    }
+}
+
+/// Scale and size of a niri output, as far as zoom needs them
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OutputInfo {
+    /// Connector name, e.g. "eDP-1"
+    pub name: String,
+    pub scale: f64,
+    /// Size in logical pixels; `None` while the output is disabled
+    pub logical_size: Option<(u32, u32)>,
+    /// Size in millimetres, if the monitor reports it
+    pub physical_size: Option<(u32, u32)>,
+}
+
+impl OutputInfo {
+    pub fn from_niri_output(output: &niri_ipc::Output) -> Self {
+        Self {
+            name: output.name.clone(),
+            scale: output.logical.map_or(1.0, |l| l.scale),
+            logical_size: output.logical.map(|l| (l.width, l.height)),
+            physical_size: output.physical_size,
         }
     }
+
+    /// How many real-world points (1/72 inch) one kitty point spans on this output
+    ///
+    /// kitty draws a point as 96/72 logical pixels. The physical size of a logical
+    /// pixel comes from the diagonals, so rotated outputs need no special case.
+    /// `None` when the monitor does not report its size.
+    pub fn point_scale(&self) -> Option<f64> {
+        let (width_mm, height_mm) = self.physical_size.filter(|&(w, h)| w > 0 && h > 0)?;
+        let (width, height) = self.logical_size.filter(|&(w, h)| w > 0 && h > 0)?;
+
+        let mm_per_pixel = f64::from(width_mm).hypot(f64::from(height_mm))
+            / f64::from(width).hypot(f64::from(height));
+        Some(mm_per_pixel * 96.0 / 25.4)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -29,15 +98,39 @@ pub enum NiriEvent {
     Create { window_id: u64, window: WindowInfo },
     #[serde(rename = "destroy")]
     Destroy { window_id: u64 },
+    /// The window now sits on a different output
+    #[serde(rename = "moved")]
+    Moved { window_id: u64, window: WindowInfo },
+    /// The focused window changed size
+    #[serde(rename = "resized")]
+    Resized { window_id: u64, window: WindowInfo },
+    /// Outputs were added, removed or reconfigured; carries the full list
+    #[serde(rename = "outputs_changed")]
+    OutputsChanged { outputs: Vec<OutputInfo> },
 }
 
 impl NiriEvent {
+    /// Short event name used for log spans and journald fields
+    pub fn kind(&self) -> &'static str {
+        match self {
+            NiriEvent::Focus { .. } => "focus",
+            NiriEvent::Blur { .. } => "blur",
+            NiriEvent::Create { .. } => "create",
+            NiriEvent::Destroy { .. } => "destroy",
+            NiriEvent::Moved { .. } => "moved",
+            NiriEvent::Resized { .. } => "resized",
+            NiriEvent::OutputsChanged { .. } => "outputs_changed",
+        }
+    }
+
     pub fn window(&self) -> Option<&WindowInfo> {
         match self {
             NiriEvent::Focus { window, .. } => Some(window),
             NiriEvent::Blur { window, .. } => Some(window),
             NiriEvent::Create { window, .. } => Some(window),
-            NiriEvent::Destroy { .. } => None,
+            NiriEvent::Moved { window, .. } => Some(window),
+            NiriEvent::Resized { window, .. } => Some(window),
+            NiriEvent::Destroy { .. } | NiriEvent::OutputsChanged { .. } => None,
         }
     }
 
@@ -47,6 +140,103 @@ impl NiriEvent {
             NiriEvent::Blur { window_id, .. } => Some(*window_id),
             NiriEvent::Create { window_id, .. } => Some(*window_id),
             NiriEvent::Destroy { window_id, .. } => Some(*window_id),
+            NiriEvent::Moved { window_id, .. } => Some(*window_id),
+            NiriEvent::Resized { window_id, .. } => Some(*window_id),
+            NiriEvent::OutputsChanged { .. } => None,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_niri_window_resolves_workspace() {
+        let window: niri_ipc::Window = serde_json::from_value(serde_json::json!({
+            "id": 7,
+            "title": "zsh",
+            "app_id": "kitty",
+            "pid": 1234,
+            "workspace_id": 2,
+            "is_focused": true,
+            "is_floating": true,
+            "is_urgent": false,
+            "layout": {
+                "pos_in_scrolling_layout": null,
+                "tile_size": [800.0, 600.0],
+                "window_size": [796, 596],
+                "tile_pos_in_workspace_view": null,
+                "window_offset_in_tile": [2.0, 2.0]
+            },
+            "focus_timestamp": null
+        }))
+        .unwrap();
+        let workspaces: Vec<niri_ipc::Workspace> = serde_json::from_value(serde_json::json!([{
+            "id": 2,
+            "idx": 3,
+            "name": "dev",
+            "output": "eDP-1",
+            "is_urgent": false,
+            "is_active": true,
+            "is_focused": true,
+            "active_window_id": 7
+        }]))
+        .unwrap();
+
+        let mut info = WindowInfo::from_niri_window(&window);
+        info.resolve_workspace(&workspaces);
+
+        assert_eq!(info.workspace_name.as_deref(), Some("dev"));
+        assert_eq!(info.workspace_idx, Some(3));
+        assert_eq!(info.output.as_deref(), Some("eDP-1"));
+        assert!(info.is_floating);
+        assert_eq!(info.tile_size, Some((800.0, 600.0)));
+        assert_eq!(info.window_size, Some((796, 596)));
+    }
+
+    #[test]
+    fn test_point_scale_from_output_size() {
+        let output: niri_ipc::Output = serde_json::from_value(serde_json::json!({
+            "name": "DP-1",
+            "make": "Dell",
+            "model": "U2723QE",
+            "serial": null,
+            "physical_size": [600, 340],
+            "modes": [{ "width": 3840, "height": 2160, "refresh_rate": 60000, "is_preferred": true }],
+            "current_mode": 0,
+            "is_custom_mode": false,
+            "vrr_supported": false,
+            "vrr_enabled": false,
+            "logical": {
+                "x": 0, "y": 0, "width": 1920, "height": 1080,
+                "scale": 2.0, "transform": "Normal"
+            }
+        }))
+        .unwrap();
+
+        let info = OutputInfo::from_niri_output(&output);
+        assert_eq!(info.scale, 2.0);
+        assert_eq!(info.logical_size, Some((1920, 1080)));
+
+        // ~0.31mm per logical pixel: a kitty point is ~1.18 real points
+        let scale = info.point_scale().unwrap();
+        assert!((scale - 1.18).abs() < 0.01, "{}", scale);
+
+        let unknown = OutputInfo {
+            physical_size: Some((0, 0)),
+            ..info
+        };
+        assert_eq!(unknown.point_scale(), None);
+    }
+
+    #[test]
+    fn test_old_traces_still_deserialize() {
+        let json = r#"{"focus":{"window_id":7,"window":{"id":7,"app_id":"kitty","pid":1234,"title":"zsh"}}}"#;
+        let event: NiriEvent = serde_json::from_str(json).unwrap();
+        let window = event.window().unwrap();
+        assert_eq!(window.pid, Some(1234));
+        assert_eq!(window.workspace_id, None);
+        assert!(!window.is_urgent);
+    }
+}
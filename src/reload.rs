@@ -0,0 +1,267 @@
+use crate::config::Config;
+use crate::kitty::conf_parser::{config_files, parse_font_size, refresh_background_opacity};
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio::signal::unix::{SignalKind, signal};
+use tokio::sync::{mpsc, watch};
+use tokio::time::sleep;
+use tracing::{debug, info, warn};
+
+/// Editors save in several steps; wait for the burst to finish before reloading
+const SETTLE_DELAY: Duration = Duration::from_millis(250);
+
+/// Reload the config whenever `path` changes or the daemon gets SIGHUP
+///
+/// `load` is rerun on each trigger so CLI overrides keep applying on top of the
+/// file. Configs that fail to load or validate are logged and skipped, leaving
+/// the previous one in place.
+pub fn watch_config<F>(initial: Config, path: Option<PathBuf>, load: F) -> watch::Receiver<Config>
+where
+    F: Fn() -> Result<Config, Box<dyn std::error::Error>> + Send + 'static,
+{
+    let (config_tx, config_rx) = watch::channel(initial);
+    let (trigger_tx, mut trigger_rx) = mpsc::unbounded_channel::<&'static str>();
+
+    let watcher = path.and_then(|path| {
+        let mut watcher = FileWatcher::new(trigger_tx.clone())?;
+        watcher.set_files(vec![path]);
+        Some(watcher)
+    });
+    forward_sighup(trigger_tx);
+
+    tokio::spawn(async move {
+        // Dropping the watcher stops file notifications
+        let _watcher = watcher;
+
+        while let Some(source) = trigger_rx.recv().await {
+            sleep(SETTLE_DELAY).await;
+            while trigger_rx.try_recv().is_ok() {}
+
+            let config = match load().map_err(|e| e.to_string()) {
+                Ok(config) => config,
+                Err(e) => {
+                    warn!(source, error = %e, "Ignoring invalid config, keeping the previous one");
+                    continue;
+                }
+            };
+
+            if same_config(&config, &config_tx.borrow()) {
+                debug!(source, "Config unchanged");
+                continue;
+            }
+
+            info!(source, "Config reloaded");
+            config_tx.send_replace(config);
+        }
+    });
+
+    config_rx
+}
+
+/// Re-resolve the baseline font size whenever kitty.conf or a file it includes
+/// changes, or the daemon gets SIGHUP
+///
+/// The include list is re-read on every change, so newly added includes are
+/// picked up too. Only changed baselines are published, and the receiver is
+/// the one to apply them; the cached `background_opacity` is refreshed on
+/// every change.
+pub fn watch_kitty_conf(path: Option<PathBuf>) -> watch::Receiver<Option<f64>> {
+    let Some(path) = path else {
+        debug!("No kitty.conf found, baseline font size will not be refreshed");
+        return watch::channel(parse_font_size(None).ok()).1;
+    };
+
+    let (baseline_tx, baseline_rx) = watch::channel(parse_font_size(Some(path.clone())).ok());
+    let (trigger_tx, mut trigger_rx) = mpsc::unbounded_channel::<&'static str>();
+
+    let mut watcher = FileWatcher::new(trigger_tx.clone());
+    if let Some(watcher) = &mut watcher {
+        watcher.set_files(config_files(&path));
+    }
+    forward_sighup(trigger_tx);
+
+    tokio::spawn(async move {
+        while let Some(source) = trigger_rx.recv().await {
+            sleep(SETTLE_DELAY).await;
+            while trigger_rx.try_recv().is_ok() {}
+
+            if let Some(watcher) = &mut watcher {
+                watcher.set_files(config_files(&path));
+            }
+
+            refresh_background_opacity(Some(path.clone()));
+            let baseline = parse_font_size(Some(path.clone())).ok();
+            if baseline == *baseline_tx.borrow() {
+                debug!(source, "Baseline font size unchanged");
+                continue;
+            }
+
+            info!(source, ?baseline, "kitty.conf baseline font size changed");
+            baseline_tx.send_replace(baseline);
+        }
+    });
+
+    baseline_rx
+}
+
+/// The next value published on `updates`, or never if there is no channel
+pub async fn next_update<T: Clone>(updates: &mut Option<watch::Receiver<T>>) -> T {
+    if let Some(rx) = updates
+        && rx.changed().await.is_ok()
+    {
+        return rx.borrow_and_update().clone();
+    }
+    std::future::pending().await
+}
+
+fn forward_sighup(trigger: mpsc::UnboundedSender<&'static str>) {
+    match signal(SignalKind::hangup()) {
+        Ok(mut hangup) => {
+            tokio::spawn(async move {
+                while hangup.recv().await.is_some() {
+                    if trigger.send("sighup").is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        Err(e) => warn!(error = %e, "Failed to install SIGHUP handler"),
+    }
+}
+
+/// Triggers when any of a set of files is written, created, replaced or removed
+///
+/// Directories are watched rather than the files themselves so atomic saves
+/// (write + rename) and files that do not exist yet are seen too.
+struct FileWatcher {
+    watcher: RecommendedWatcher,
+    files: Arc<RwLock<HashSet<PathBuf>>>,
+    dirs: HashSet<PathBuf>,
+}
+
+impl FileWatcher {
+    fn new(trigger: mpsc::UnboundedSender<&'static str>) -> Option<Self> {
+        let files = Arc::new(RwLock::new(HashSet::<PathBuf>::new()));
+        let watched = Arc::clone(&files);
+
+        let watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+            if let Ok(event) = event
+                && !matches!(event.kind, EventKind::Access(_))
+                && event
+                    .paths
+                    .iter()
+                    .any(|p| watched.read().unwrap().contains(p))
+            {
+                let _ = trigger.send("file");
+            }
+        })
+        .map_err(|e| warn!(error = %e, "Failed to create file watcher"))
+        .ok()?;
+
+        Some(Self {
+            watcher,
+            files,
+            dirs: HashSet::new(),
+        })
+    }
+
+    /// Replace the watched files, adding watches for directories not seen before
+    fn set_files(&mut self, files: Vec<PathBuf>) {
+        for dir in files.iter().filter_map(|path| path.parent()) {
+            if self.dirs.contains(dir) {
+                continue;
+            }
+            match self.watcher.watch(dir, RecursiveMode::NonRecursive) {
+                Ok(()) => {
+                    self.dirs.insert(dir.to_path_buf());
+                }
+                Err(e) => warn!(
+                    dir = %dir.display(),
+                    error = %e,
+                    "Not watching directory, reload with SIGHUP instead"
+                ),
+            }
+        }
+
+        debug!(files = ?files, "Watching files");
+        *self.files.write().unwrap() = files.into_iter().collect();
+    }
+}
+
+fn same_config(a: &Config, b: &Config) -> bool {
+    match (serde_json::to_value(a), serde_json::to_value(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use figment2::Figment;
+    use figment2::providers::{Format, Toml};
+
+    fn load_from(path: PathBuf) -> impl Fn() -> Result<Config, Box<dyn std::error::Error>> {
+        move || {
+            let config: Config = Figment::new().merge(Toml::file(&path)).extract()?;
+            config.zoom.validate()?;
+            Ok(config)
+        }
+    }
+
+    async fn next_change<T: Clone>(rx: &mut watch::Receiver<T>) -> Option<T> {
+        tokio::time::timeout(Duration::from_secs(5), rx.changed())
+            .await
+            .ok()?
+            .ok()?;
+        Some(rx.borrow_and_update().clone())
+    }
+
+    #[tokio::test]
+    async fn test_file_changes_are_published() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(&path, "[zoom]\nadditive = 2.0\n").unwrap();
+
+        let initial = load_from(path.clone())().unwrap();
+        let mut rx = watch_config(initial, Some(path.clone()), load_from(path.clone()));
+
+        std::fs::write(&path, "[zoom]\nadditive = 4.0\n").unwrap();
+        let config = next_change(&mut rx).await.expect("reload after edit");
+        assert_eq!(config.zoom.additive, Some(4.0));
+
+        // Invalid configs are skipped; the next valid one still gets through
+        std::fs::write(&path, "[zoom]\nadditive = 4.0\nabsolute = 9.0\n").unwrap();
+        sleep(SETTLE_DELAY * 3).await;
+        std::fs::write(&path, "[zoom]\nmultiplicative = 1.5\n").unwrap();
+        let config = next_change(&mut rx).await.expect("reload after fix");
+        assert_eq!(config.zoom.multiplicative, Some(1.5));
+        assert_eq!(config.zoom.additive, None);
+    }
+
+    #[tokio::test]
+    async fn test_kitty_conf_includes_are_watched() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("kitty.conf");
+        std::fs::write(&path, "font_size 11\ninclude fonts.conf\n").unwrap();
+
+        let mut rx = watch_kitty_conf(Some(path.clone()));
+        assert_eq!(*rx.borrow(), Some(11.0));
+
+        // The include does not exist yet; creating it still counts
+        std::fs::write(dir.path().join("fonts.conf"), "font_size 14\n").unwrap();
+        assert_eq!(next_change(&mut rx).await, Some(Some(14.0)));
+
+        std::fs::write(
+            dir.path().join("fonts.conf"),
+            "font_size 14\ninclude more.conf\n",
+        )
+        .unwrap();
+        sleep(SETTLE_DELAY * 3).await;
+        std::fs::write(dir.path().join("more.conf"), "font_size 9.5\n").unwrap();
+        assert_eq!(next_change(&mut rx).await, Some(Some(9.5)));
+    }
+}
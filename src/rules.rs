@@ -0,0 +1,320 @@
+use crate::config::{Config, ZoomConfig};
+use crate::niri::types::WindowInfo;
+use regex::Regex;
+use schemars::{JsonSchema, Schema, SchemaGenerator, json_schema};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::borrow::Cow;
+use std::sync::Arc;
+
+/// Name reported for windows zoomed by the top-level `app_id` and `[zoom]`
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// A regular expression that (de)serializes as its source string
+#[derive(Debug, Clone)]
+pub struct Pattern(Regex);
+
+impl Pattern {
+    pub fn new(pattern: &str) -> Result<Self, regex::Error> {
+        Regex::new(pattern).map(Self)
+    }
+
+    pub fn is_match(&self, text: &str) -> bool {
+        self.0.is_match(text)
+    }
+}
+
+impl Serialize for Pattern {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.0.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for Pattern {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let pattern = String::deserialize(deserializer)?;
+        Self::new(&pattern).map_err(serde::de::Error::custom)
+    }
+}
+
+impl JsonSchema for Pattern {
+    fn schema_name() -> Cow<'static, str> {
+        "Pattern".into()
+    }
+
+    fn inline_schema() -> bool {
+        true
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        json_schema!({
+            "type": "string",
+            "format": "regex"
+        })
+    }
+}
+
+/// One `[[rule]]` table: match criteria plus the zoom profile to apply
+///
+/// Every criterion that is set must match; a rule with none matches every window.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct Rule {
+    /// Label used in logs and `status`; defaults to `rule-<n>`
+    pub name: Option<String>,
+
+    /// Exact app_id
+    pub app_id: Option<String>,
+
+    /// Regex matched against app_id
+    pub app_id_regex: Option<Pattern>,
+
+    /// Regex matched against the window title
+    pub title: Option<Pattern>,
+
+    /// Workspace name
+    pub workspace: Option<String>,
+
+    /// Workspace index on its output, as shown by niri (1-based)
+    pub workspace_index: Option<u8>,
+
+    /// Output (monitor) connector name, e.g. "eDP-1"
+    pub output: Option<String>,
+
+    /// Match only floating (true) or only tiled (false) windows
+    pub floating: Option<bool>,
+
+    /// Leave matching windows alone
+    pub exclude: bool,
+
+    /// Zoom profile for matching windows; inherits `[zoom]` when omitted
+    pub zoom: Option<ZoomConfig>,
+}
+
+impl Rule {
+    pub fn matches(&self, window: &WindowInfo) -> bool {
+        let app_id = window.app_id.as_deref();
+        let title = window.title.as_deref();
+
+        self.app_id.as_deref().is_none_or(|id| app_id == Some(id))
+            && self
+                .app_id_regex
+                .as_ref()
+                .is_none_or(|re| app_id.is_some_and(|id| re.is_match(id)))
+            && self
+                .title
+                .as_ref()
+                .is_none_or(|re| title.is_some_and(|t| re.is_match(t)))
+            && self
+                .workspace
+                .as_deref()
+                .is_none_or(|name| window.workspace_name.as_deref() == Some(name))
+            && self
+                .workspace_index
+                .is_none_or(|idx| window.workspace_idx == Some(idx))
+            && self
+                .output
+                .as_deref()
+                .is_none_or(|output| window.output.as_deref() == Some(output))
+            && self
+                .floating
+                .is_none_or(|floating| window.is_floating == floating)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.exclude && self.zoom.is_some() {
+            return Err("a rule cannot set both exclude and zoom".to_string());
+        }
+        if let Some(zoom) = &self.zoom {
+            zoom.validate()?;
+        }
+        Ok(())
+    }
+}
+
+/// Zoom settings chosen for one window, with the name of the rule that picked them
+#[derive(Debug, Clone)]
+pub struct ZoomProfile {
+    pub name: Arc<str>,
+    pub zoom: ZoomConfig,
+}
+
+/// Ordered rules plus the top-level `app_id`/`[zoom]` fallback
+///
+/// The first matching rule wins. Windows no rule matches are zoomed with `[zoom]`
+/// when their app_id equals the top-level `app_id`, as before rules existed.
+#[derive(Debug, Clone, Default)]
+pub struct Rules {
+    rules: Vec<(Arc<str>, Rule)>,
+    app_id: String,
+    zoom: ZoomConfig,
+}
+
+impl Rules {
+    pub fn new(app_id: String, zoom: ZoomConfig, rules: Vec<Rule>) -> Self {
+        let rules = rules
+            .into_iter()
+            .enumerate()
+            .map(|(i, rule)| {
+                let name = rule
+                    .name
+                    .clone()
+                    .unwrap_or_else(|| format!("rule-{}", i + 1));
+                (Arc::from(name), rule)
+            })
+            .collect();
+
+        Self {
+            rules,
+            app_id,
+            zoom,
+        }
+    }
+
+    pub fn from_config(config: &Config) -> Self {
+        Self::new(
+            config.app_id.clone(),
+            config.zoom.clone(),
+            config.rules.clone(),
+        )
+    }
+
+    /// The top-level `app_id` windows fall back to when no rule matches
+    pub fn app_id(&self) -> &str {
+        &self.app_id
+    }
+
+    /// The top-level `[zoom]` profile
+    pub fn default_zoom(&self) -> &ZoomConfig {
+        &self.zoom
+    }
+
+    /// Whether the window is of interest at all, including windows a rule excludes
+    pub fn considers(&self, window: &WindowInfo) -> bool {
+        window.app_id.as_deref() == Some(self.app_id.as_str())
+            || self.rules.iter().any(|(_, rule)| rule.matches(window))
+    }
+
+    /// The zoom profile for a window, or `None` if it should not be zoomed
+    pub fn resolve(&self, window: &WindowInfo) -> Option<ZoomProfile> {
+        if let Some((name, rule)) = self.rules.iter().find(|(_, rule)| rule.matches(window)) {
+            if rule.exclude {
+                return None;
+            }
+            return Some(ZoomProfile {
+                name: Arc::clone(name),
+                zoom: rule.zoom.clone().unwrap_or_else(|| self.zoom.clone()),
+            });
+        }
+
+        (window.app_id.as_deref() == Some(self.app_id.as_str())).then(|| ZoomProfile {
+            name: Arc::from(DEFAULT_PROFILE),
+            zoom: self.zoom.clone(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use figment2::Figment;
+    use figment2::providers::{Format, Toml};
+
+    fn window(app_id: &str, title: &str) -> WindowInfo {
+        WindowInfo {
+            id: 1,
+            app_id: Some(app_id.to_string()),
+            pid: Some(100),
+            title: Some(title.to_string()),
+            workspace_id: Some(3),
+            workspace_name: Some("dev".to_string()),
+            workspace_idx: Some(2),
+            output: Some("eDP-1".to_string()),
+            is_floating: false,
+            ..Default::default()
+        }
+    }
+
+    fn rules(toml: &str) -> Rules {
+        let config: Config = Figment::new().merge(Toml::string(toml)).extract().unwrap();
+        Rules::from_config(&config)
+    }
+
+    #[test]
+    fn test_first_matching_rule_wins() {
+        let rules = rules(
+            r#"
+            [zoom]
+            additive = 2.0
+
+            [[rule]]
+            name = "editor"
+            title = "^nvim"
+            zoom = { absolute = 20.0 }
+
+            [[rule]]
+            app_id = "kitty"
+            output = "eDP-1"
+            zoom = { multiplicative = 1.5 }
+            "#,
+        );
+
+        let profile = rules.resolve(&window("kitty", "nvim main.rs")).unwrap();
+        assert_eq!(&*profile.name, "editor");
+        assert_eq!(profile.zoom.absolute, Some(20.0));
+
+        let profile = rules.resolve(&window("kitty", "zsh")).unwrap();
+        assert_eq!(&*profile.name, "rule-2");
+        assert_eq!(profile.zoom.multiplicative, Some(1.5));
+    }
+
+    #[test]
+    fn test_fallback_and_exclude() {
+        let rules = rules(
+            r#"
+            [zoom]
+            additive = 2.0
+
+            [[rule]]
+            app_id = "kitty"
+            floating = true
+            exclude = true
+
+            [[rule]]
+            app_id_regex = "^foot"
+            workspace = "dev"
+            workspace_index = 2
+            "#,
+        );
+
+        let profile = rules.resolve(&window("kitty", "zsh")).unwrap();
+        assert_eq!(&*profile.name, DEFAULT_PROFILE);
+        assert_eq!(profile.zoom.additive, Some(2.0));
+
+        let mut floating = window("kitty", "scratchpad");
+        floating.is_floating = true;
+        assert!(rules.resolve(&floating).is_none());
+        assert!(rules.considers(&floating));
+
+        let profile = rules.resolve(&window("footclient", "zsh")).unwrap();
+        assert_eq!(profile.zoom.additive, Some(2.0));
+
+        let firefox = window("firefox", "docs");
+        assert!(rules.resolve(&firefox).is_none());
+        assert!(!rules.considers(&firefox));
+    }
+
+    #[test]
+    fn test_invalid_rules_are_rejected() {
+        let result: Result<Config, _> = Figment::new()
+            .merge(Toml::string("[[rule]]\ntitle = \"(unclosed\"\n"))
+            .extract();
+        assert!(result.is_err());
+
+        let rule = Rule {
+            exclude: true,
+            zoom: Some(ZoomConfig::default()),
+            ..Default::default()
+        };
+        assert!(rule.validate().is_err());
+    }
+}